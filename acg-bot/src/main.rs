@@ -18,6 +18,18 @@ async fn main() {
             database::Database::error("no connection string")
         }
     };
+    match db.migrate().await {
+        Ok(v) if v.is_empty() => log::info!("Database schema is up to date"),
+        Ok(v) => log::info!("Applied database migrations {:?}", v),
+        // everything else still runs without a database
+        Err(e @ (database::Error::NoDatabase | database::Error::ClientGetError(_))) => {
+            log::warn!("skipped database migrations: {}", e)
+        }
+        Err(e) => {
+            log::error!("failed to migrate database: {}", e);
+            std::process::exit(1);
+        }
+    }
 
     #[cfg(feature = "sappho")]
     let sa_bot = sappho_bot::Bot::new(db.clone());
@@ -17,7 +17,7 @@ FROM
       ) AS bucket
     FROM
       (
-        SELECT *, split_part(type, ' ', 1) AS kind
+        SELECT *, split_part(song_type, ' ', 1) AS kind
         FROM amq_songs
         UNION
         SELECT *, 'All' AS kind
@@ -6,7 +6,7 @@ SELECT
   SUM(p.times_played)
 FROM song_plays p
 LEFT JOIN (
-  SELECT *, SPLIT_PART(type, ' ', 1) AS kind FROM amq_songs
+  SELECT *, SPLIT_PART(song_type, ' ', 1) AS kind FROM amq_songs
   UNION
   SELECT *, 'All' FROM amq_songs
 ) s
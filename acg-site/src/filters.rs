@@ -1,5 +1,5 @@
 use crate::reply::JsonReply;
-use crate::song::{query_song, search_song, update_song_data};
+use crate::song::{query_song, search_song, update_song_data, update_song_data_batch};
 use crate::stats;
 use database::Database;
 use warp::{Filter, Rejection, Reply};
@@ -63,12 +63,25 @@ pub fn song_data_post(
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     warp::path!("song")
         .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
         .and(warp::body::json())
         .and(with_db(db))
         .then(update_song_data)
         .map(JsonReply::to_response)
 }
 
+pub fn song_data_batch_post(
+    db: Database,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("songs")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and(with_db(db))
+        .then(update_song_data_batch)
+        .map(JsonReply::to_response)
+}
+
 pub fn song_stats(db: Database) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     let song_stats_page = warp::path::end().and(warp::filters::fs::file("public/stats.html"));
 
@@ -83,15 +96,23 @@ pub fn song_stats(db: Database) -> impl Filter<Extract = (impl Reply,), Error =
         .then(|num_bins: u32, db: Database| stats::guess_rate_difficulty(db, num_bins, 0))
         .map(JsonReply::to_response);
     let song_stats_difficulty2 = warp::path!("difficulty2" / u32)
-        .and(with_db(db))
+        .and(with_db(db.clone()))
         .then(|num_bins: u32, db: Database| stats::guess_rate_difficulty(db, num_bins, 1))
         .map(JsonReply::to_response);
 
+    // e.g. /stats/daily?player=name&tz=Europe/London&days=7
+    let song_stats_daily = warp::path!("daily")
+        .and(with_db(db))
+        .and(warp::query::<stats::DailyQuery>())
+        .then(stats::plays_daily)
+        .map(JsonReply::to_response);
+
     warp::path!("stats" / ..).and(warp::get()).and(
         song_stats_page
             .or(song_stats_vintage)
             .or(song_stats_difficulty)
-            .or(song_stats_difficulty2),
+            .or(song_stats_difficulty2)
+            .or(song_stats_daily),
     )
 }
 
@@ -20,11 +20,24 @@ async fn main() {
             database::Database::error("no connection string")
         }
     };
+    match db.migrate().await {
+        Ok(v) if v.is_empty() => log::info!("Database schema is up to date"),
+        Ok(v) => log::info!("Applied database migrations {:?}", v),
+        // everything else still runs without a database
+        Err(e @ (database::Error::NoDatabase | database::Error::ClientGetError(_))) => {
+            log::warn!("skipped database migrations: {}", e)
+        }
+        Err(e) => {
+            log::error!("failed to migrate database: {}", e);
+            std::process::exit(1);
+        }
+    }
 
     let uptime = filters::uptime(start_time);
 
     // database access
     let song_data_post = filters::song_data_post(db.clone());
+    let song_data_batch_post = filters::song_data_batch_post(db.clone());
     let song_stats = filters::song_stats(db.clone());
     let song_search = filters::song_search(db);
 
@@ -34,6 +47,7 @@ async fn main() {
     use warp::Filter;
     let routes = uptime
         .or(song_data_post)
+        .or(song_data_batch_post)
         .or(song_stats)
         .or(song_search)
         .or(files)
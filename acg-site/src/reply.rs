@@ -8,6 +8,10 @@ use warp::Reply;
 pub enum JsonReply {
     Success(warp::reply::Json),
     BadRequest(Cow<'static, str>),
+    // no valid credentials
+    Unauthorized(Cow<'static, str>),
+    // valid credentials that aren't allowed to do this
+    Forbidden(Cow<'static, str>),
     Error(Cow<'static, str>),
 }
 
@@ -19,6 +23,15 @@ impl JsonReply {
             JsonReply::BadRequest(s) => {
                 warp::reply::with_status(s, StatusCode::BAD_REQUEST).into_response()
             }
+            JsonReply::Unauthorized(s) => warp::reply::with_header(
+                warp::reply::with_status(s, StatusCode::UNAUTHORIZED),
+                "WWW-Authenticate",
+                "Bearer",
+            )
+            .into_response(),
+            JsonReply::Forbidden(s) => {
+                warp::reply::with_status(s, StatusCode::FORBIDDEN).into_response()
+            }
             JsonReply::Error(s) => {
                 warp::reply::with_status(s, StatusCode::INTERNAL_SERVER_ERROR).into_response()
             }
@@ -1,11 +1,64 @@
 use crate::reply::JsonReply;
+use crate::Error;
 
-pub async fn update_song_data(data: database::SongData, db: database::Database) -> JsonReply {
-    if data.player_name.is_none() {
-        JsonReply::BadRequest("no player_name provided".into())
-    } else {
-        // TODO: map user auth error to bad request
-        db.update_song_data(data).await.into()
+// Plays are submitted as the player the API key belongs to
+pub async fn update_song_data(
+    auth: Option<String>,
+    mut data: database::SongData,
+    db: database::Database,
+) -> JsonReply {
+    let player = match authenticate(auth, &db).await {
+        Ok(player) => player,
+        Err(reply) => return reply,
+    };
+    // the name is optional now, but a different one is a mistake
+    if matches!(data.player_name, Some(ref name) if *name != player) {
+        return JsonReply::Forbidden("API key is for a different player".into());
+    }
+    data.player_name = Some(player);
+    db.update_song_data(data).await.into()
+}
+
+// Most plays allowed in one batch, more than a long game
+const MAX_BATCH_LEN: usize = 500;
+
+// Every play from a game at once, each one is reported on separately
+pub async fn update_song_data_batch(
+    auth: Option<String>,
+    batch: database::SongDataBatch,
+    db: database::Database,
+) -> JsonReply {
+    let player = match authenticate(auth, &db).await {
+        Ok(player) => player,
+        Err(reply) => return reply,
+    };
+    if batch.batch_id.is_empty() {
+        return JsonReply::BadRequest("no batch_id provided".into());
+    }
+    if batch.songs.len() > MAX_BATCH_LEN {
+        return JsonReply::BadRequest(format!("Max songs allowed is {}", MAX_BATCH_LEN).into());
+    }
+    let other_player = batch
+        .songs
+        .iter()
+        .any(|data| matches!(data.player_name, Some(ref name) if *name != player));
+    if other_player {
+        return JsonReply::Forbidden("API key is for a different player".into());
+    }
+    db.update_song_data_batch(&player, batch).await.into()
+}
+
+// Returns the player from an `Authorization: Bearer <key>` header
+async fn authenticate(auth: Option<String>, db: &database::Database) -> Result<String, JsonReply> {
+    let key = auth
+        .as_deref()
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or_else(|| JsonReply::Unauthorized("no API key provided".into()))?;
+    match db.authenticate_api_key(key.trim()).await {
+        Ok(Some(user)) if user.revoked => Err(JsonReply::Forbidden("API key revoked".into())),
+        Ok(Some(user)) => Ok(user.player_name),
+        Ok(None) => Err(JsonReply::Unauthorized("invalid API key".into())),
+        Err(e) => Err(JsonReply::Error(Error::from(e).to_string().into())),
     }
 }
 
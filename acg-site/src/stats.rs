@@ -11,6 +11,14 @@ pub struct SongStatVintage {
     times_played: i64,
 }
 
+#[derive(serde::Deserialize)]
+pub struct DailyQuery {
+    player: String,
+    // IANA timezone name, e.g. `Europe/London`, otherwise the player's own timezone
+    tz: Option<String>,
+    days: Option<u32>,
+}
+
 // TODO: clean up this type
 #[derive(serde::Serialize, Default)]
 pub struct SongStatDifficulty {
@@ -85,3 +93,28 @@ pub async fn guess_rate_difficulty(db: database::Database, num_bins: u32, kind:
     }
     .into()
 }
+
+pub async fn plays_daily(db: database::Database, query: DailyQuery) -> JsonReply {
+    let days = query.days.unwrap_or(30);
+    if days > 1000 {
+        return JsonReply::BadRequest(Cow::Borrowed("Max days allowed is 1000"));
+    }
+    // checked here so an unknown timezone isn't a database error
+    let tz = match query.tz.map(|tz| tz.parse::<database::Tz>()).transpose() {
+        Ok(tz) => tz,
+        Err(e) => return JsonReply::BadRequest(Cow::Owned(e.to_string())),
+    };
+
+    // the player's own timezone if none was asked for
+    let result = async {
+        let tz = match tz {
+            Some(tz) => tz,
+            None => db
+                .get_user_timezone(&query.player)
+                .await?
+                .unwrap_or(database::Tz::UTC),
+        };
+        db.get_daily_plays(&query.player, tz, days as i64).await
+    };
+    result.await.into()
+}
@@ -9,13 +9,15 @@ pub(crate) struct Data {
     pub quiz: SongArtistQuiz,
     // one global audio track instance for now
     pub track: Arc<RwLock<Option<CurrentTrack>>>,
+    pub db: database::Database,
 }
 
 impl Data {
     pub(crate) fn new(db: database::Database) -> Self {
         Data {
-            quiz: SongArtistQuiz::new(db),
+            quiz: SongArtistQuiz::new(db.clone()),
             track: Arc::new(RwLock::new(None)),
+            db,
         }
     }
 }
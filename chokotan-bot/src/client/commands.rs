@@ -1,6 +1,7 @@
 mod general;
 mod quiz;
 mod songs;
+mod stats;
 
 use crate::{Data, Error};
 type Command = poise::Command<Data, Error>;
@@ -10,6 +11,7 @@ pub(crate) fn commands() -> Vec<Command> {
         .chain(general::commands())
         .chain(songs::commands())
         .chain(quiz::commands())
+        .chain(stats::commands())
         .map(|mut c| {
             c.name = c.name.replace("_", "");
             c
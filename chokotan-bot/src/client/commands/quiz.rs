@@ -11,6 +11,7 @@ use songbird::{Call, EventContext};
 
 mod config;
 mod settings;
+mod team;
 
 use crate::{Data, Error};
 type Context<'a> = poise::Context<'a, Data, Error>;
@@ -23,14 +24,23 @@ use crate::quiz::SongArtistQuiz;
 use crate::voice;
 
 pub(super) fn commands() -> impl IntoIterator<Item = Command> {
-    [start_quiz(), stop_quiz(), skip_song()]
-        .into_iter()
-        .chain(settings::commands())
-        .chain(config::commands())
-        .map(|mut cmd| {
-            cmd.category = Some("Quiz");
-            cmd
-        })
+    [
+        start_quiz(),
+        resume_quiz(),
+        stop_quiz(),
+        skip_song(),
+        scoreboard(),
+        hint(),
+        explain(),
+    ]
+    .into_iter()
+    .chain(settings::commands())
+    .chain(team::commands())
+    .chain(config::commands())
+    .map(|mut cmd| {
+        cmd.category = Some("Quiz");
+        cmd
+    })
 }
 
 // TODO:
@@ -62,7 +72,32 @@ async fn start_quiz(
     let qc = quiz.clone();
     let curr_track = ctx.data().track.clone();
     let http = ctx.serenity_context().http.clone();
-    let quiz_task = tokio::task::spawn(run_quiz(qc, handler, curr_track, channel_id, http));
+    let quiz_task = tokio::task::spawn(run_quiz(qc, handler, curr_track, channel_id, http, 0));
+    quiz.set_task(quiz_task);
+
+    Ok(())
+}
+
+/// Carry on the quiz that was interrupted in this channel by a restart
+#[poise::command(slash_command, prefix_command, aliases("resume"))]
+async fn resume_quiz(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or(Error::NotInGuild)?;
+    let context = ctx.serenity_context();
+    let handler = voice::call_handler(context, guild_id).await?;
+    voice::check_in_channel(&handler).await?;
+
+    let channel_id = ctx.channel_id();
+    let quiz = &ctx.data().quiz;
+    let song_num = quiz.resume(channel_id)?;
+    ctx.reply(format!("Resuming quiz after song {}.", song_num))
+        .await?;
+
+    let qc = quiz.clone();
+    let curr_track = ctx.data().track.clone();
+    let http = ctx.serenity_context().http.clone();
+    let quiz_task = tokio::task::spawn(run_quiz(
+        qc, handler, curr_track, channel_id, http, song_num,
+    ));
     quiz.set_task(quiz_task);
 
     Ok(())
@@ -89,6 +124,99 @@ async fn skip_song(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+// Only show this many players so it fits in an embed
+const SCOREBOARD_LENGTH: usize = 20;
+
+/// Show the points for each player in the current quiz
+#[poise::command(slash_command, prefix_command, aliases("leaderboard", "scores"))]
+async fn scoreboard(ctx: Context<'_>) -> Result<(), Error> {
+    let scores = ctx.data().quiz.scoreboard()?;
+    if scores.is_empty() {
+        ctx.reply("No points scored yet.").await?;
+        return Ok(());
+    }
+
+    let mut desc = String::new();
+    for (i, score) in scores.iter().take(SCOREBOARD_LENGTH).enumerate() {
+        // players are stored by user id
+        desc += &format!(
+            "**{}.** <@{}> - {:.1} ({} correct)\n",
+            i + 1,
+            score.player,
+            score.points,
+            score.num_correct
+        );
+    }
+    ctx.send(|cm| cm.embed(|ce| ce.title("Scoreboard").description(desc)))
+        .await?;
+    Ok(())
+}
+
+/// Reveal part of the answer for the current song
+#[poise::command(slash_command, prefix_command)]
+async fn hint(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(hint) = ctx.data().quiz.hint()? else {
+        ctx.reply("No song is playing.").await?;
+        return Ok(());
+    };
+
+    let fields: Vec<_> = [
+        ("Song Name", hint.song_name),
+        ("Artist", hint.artist),
+        ("Anime", hint.anime),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|v| (name, format!("`{}`", v), false)))
+    .collect();
+    ctx.send(|cm| {
+        cm.embed(|ce| ce.title(format!("Hint {}", hint.level)).fields(fields))
+            .reply(true)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Explain how a guess was scored against a finished song, the last one by default
+#[poise::command(slash_command, prefix_command, aliases("why"))]
+async fn explain(
+    ctx: Context<'_>,
+    #[description = "Song number to check against"] song: Option<i64>,
+    #[description = "Guess to explain"]
+    #[rest]
+    guess: String,
+) -> Result<(), Error> {
+    let (song_num, explanation) = ctx.data().quiz.explain(&guess, song)?;
+
+    let fields: Vec<_> = [
+        ("Song Name", explanation.song_name),
+        ("Artist", explanation.artist),
+        ("Anime", explanation.anime),
+    ]
+    .into_iter()
+    .filter_map(|(name, e)| {
+        let e = e?;
+        let mark = if e.accepted { "✅" } else { "❌" };
+        let title = format!("{} {} {:.1}%", name, mark, e.score * 100.0);
+        let diff: String = e.diff.iter().map(ToString::to_string).collect();
+        let mut value = format!("```\n{}\n```", diff);
+        if !e.steps.is_empty() {
+            value += &format!("\n{}", e.steps.join(", "));
+        }
+        Some((title, value, false))
+    })
+    .collect();
+    ctx.send(|cm| {
+        cm.embed(|ce| {
+            ce.title(format!("Song {}", song_num))
+                .description("`[+x]` missing, `[-x]` extra, `[x→y]` wrong")
+                .fields(fields)
+        })
+        .reply(true)
+    })
+    .await?;
+    Ok(())
+}
+
 struct TrackEndHandler(CancellationToken);
 
 #[async_trait]
@@ -108,8 +236,11 @@ async fn run_quiz(
     curr_track: Arc<RwLock<Option<CurrentTrack>>>,
     channel_id: ChannelId,
     http: Arc<Http>,
+    // the last song played, when resuming a quiz
+    first_song: i64,
 ) -> Result<(), Error> {
     let quiz_token = quiz.cancel_token();
+    tokio::task::spawn(quiz.clone().save_snapshots(quiz_token.clone()));
 
     // struct passed into the loader
     //  to get the song info and sample used
@@ -117,7 +248,7 @@ async fn run_quiz(
     let mut next_load = Box::pin(quiz.load_next_song(&mut song_data));
     let mut load_result = None;
 
-    let mut song_num = 0;
+    let mut song_num = first_song;
     let mut quit = false;
     let mut num_errors = 0;
     while !quit {
@@ -233,6 +364,9 @@ async fn run_quiz(
 
         // Update embed status
         song_msg.set_playing().await?;
+        if let Some(choices) = quiz.choices()? {
+            song_msg.set_choices(song_num, &choices).await?;
+        }
 
         // Wait for next song to be played or for quiz cancellation
         loop {
@@ -247,9 +381,16 @@ async fn run_quiz(
             }
         }
         log::trace!("Quiz song finished");
+        // nothing to do if the quiz was stopped
+        let _ = quiz.finish_song();
 
         // Update embed to status of last song
-        song_msg.set_finished(song_info).await?;
+        //  the scores are gone if the quiz was stopped
+        let scores = quiz.song_scores().unwrap_or_default();
+        let team_scores = quiz.team_song_scores().unwrap_or_default();
+        song_msg
+            .set_finished(song_info, &scores, &team_scores)
+            .await?;
     }
     Ok(())
 }
@@ -7,7 +7,8 @@ macro_rules! SETTINGS_FORMAT_STRING {
         **Display Threshold: ** {:.1}%\n\
         **Space Sensitive: **{}\n\
         **Case Sensitive: **{}\n\
-        **Punctuation Sensitive: **{}\n
+        **Punctuation Sensitive: **{}\n\
+        **Scorer: **{}\n
     "
     };
 }
@@ -136,5 +137,6 @@ fn create_settings_desc(settings: &GuessSettings) -> String {
         SETTINGS_CHECKMARK!(settings.space_sensitive),
         SETTINGS_CHECKMARK!(settings.case_sensitive),
         SETTINGS_CHECKMARK!(settings.punc_sensitive),
+        settings.scorer.name(),
     )
 }
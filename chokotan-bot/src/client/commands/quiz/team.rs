@@ -0,0 +1,103 @@
+use crate::{Data, Error};
+type Context<'a> = poise::Context<'a, Data, Error>;
+type Command = poise::Command<Data, Error>;
+
+pub(super) fn commands() -> impl IntoIterator<Item = Command> {
+    [
+        create_team(),
+        join_team(),
+        leave_team(),
+        teams(),
+        team_scoreboard(),
+    ]
+}
+
+/// Create a team for players to join
+#[poise::command(slash_command, prefix_command)]
+async fn create_team(
+    ctx: Context<'_>,
+    #[description = "Name of the team"]
+    #[rest]
+    name: String,
+) -> Result<(), Error> {
+    ctx.data().quiz.create_team(&name)?;
+    ctx.reply(format!("Created team **{}**.", name.trim()))
+        .await?;
+    Ok(())
+}
+
+/// Join a team, leaving any team you were in
+#[poise::command(slash_command, prefix_command)]
+async fn join_team(
+    ctx: Context<'_>,
+    #[description = "Name of the team"]
+    #[rest]
+    name: String,
+) -> Result<(), Error> {
+    ctx.data().quiz.join_team(ctx.author().id, &name)?;
+    ctx.reply(format!("Joined team **{}**.", name.trim()))
+        .await?;
+    Ok(())
+}
+
+/// Leave your team
+#[poise::command(slash_command, prefix_command)]
+async fn leave_team(ctx: Context<'_>) -> Result<(), Error> {
+    let reply = match ctx.data().quiz.leave_team(ctx.author().id) {
+        Some(team) => format!("Left team **{}**.", team),
+        None => "You're not in a team.".to_string(),
+    };
+    ctx.reply(reply).await?;
+    Ok(())
+}
+
+/// Show the teams and who is in them
+#[poise::command(slash_command, prefix_command)]
+async fn teams(ctx: Context<'_>) -> Result<(), Error> {
+    let teams = ctx.data().quiz.teams();
+    if teams.is_empty() {
+        ctx.reply("No teams yet.").await?;
+        return Ok(());
+    }
+
+    let fields: Vec<_> = teams
+        .list()
+        .map(|(team, members)| {
+            // players are stored by user id
+            let members: Vec<_> = members.iter().map(|p| format!("<@{}>", p)).collect();
+            let value = if members.is_empty() {
+                "-".to_string()
+            } else {
+                members.join(", ")
+            };
+            (team, value, false)
+        })
+        .collect();
+    ctx.send(|cm| cm.embed(|ce| ce.title("Teams").fields(fields)))
+        .await?;
+    Ok(())
+}
+
+/// Show the points for each team in the current quiz
+#[poise::command(slash_command, prefix_command, aliases("team_scores"))]
+async fn team_scoreboard(ctx: Context<'_>) -> Result<(), Error> {
+    let scores = ctx.data().quiz.team_scoreboard()?;
+    if scores.is_empty() {
+        ctx.reply("No team points scored yet.").await?;
+        return Ok(());
+    }
+
+    let mut desc = String::new();
+    for (i, score) in scores.iter().enumerate() {
+        desc += &format!(
+            "**{}.** {} - {:.1} ({} correct)\n",
+            i + 1,
+            score.player,
+            score.points,
+            score.num_correct
+        );
+    }
+    ctx.send(|cm| cm.embed(|ce| ce.title("Team Scoreboard").description(desc)))
+        .await?;
+    Ok(())
+}
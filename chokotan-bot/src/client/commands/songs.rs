@@ -117,6 +117,7 @@ async fn play(
                 if let Some(m) = x {
                     match m {
                         SongMessage::StartSample(_) => (),
+                        SongMessage::Metadata(m) => song_info.set_metadata(*m).await?,
                         SongMessage::TotalDuration(t) => song_info.update_total_duration(t.seconds as f64 + t.frac).await?,
                         SongMessage::Update(t) => {
                             if last_update.0.elapsed() >= UPDATE_DURATION {
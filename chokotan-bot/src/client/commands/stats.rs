@@ -0,0 +1,90 @@
+use crate::{Data, Error};
+type Context<'a> = poise::Context<'a, Data, Error>;
+type Command = poise::Command<Data, Error>;
+
+pub(super) fn commands() -> impl IntoIterator<Item = Command> {
+    [amq_stats(), set_timezone()].into_iter().map(|mut cmd| {
+        cmd.category = Some("Stats");
+        cmd
+    })
+}
+
+// Days to show in the stats
+const STATS_DAYS: i64 = 14;
+
+fn parse_timezone(tz: &str) -> Result<database::Tz, Error> {
+    tz.parse()
+        .map_err(|_| Error::InvalidTimezone(tz.to_string()))
+}
+
+/// Show the songs an AMQ player has played each day
+#[poise::command(slash_command, prefix_command)]
+async fn amq_stats(
+    ctx: Context<'_>,
+    #[description = "AMQ player name"] player: String,
+    #[description = "Timezone for the days, e.g. Europe/London"] timezone: Option<String>,
+) -> Result<(), Error> {
+    let db = &ctx.data().db;
+    // the player's own timezone if none is given
+    let tz = match timezone {
+        Some(tz) => parse_timezone(&tz)?,
+        None => db
+            .get_user_timezone(&player)
+            .await
+            .map_err(Error::StatsErr)?
+            .unwrap_or(database::Tz::UTC),
+    };
+    let days = db
+        .get_daily_plays(&player, tz, STATS_DAYS)
+        .await
+        .map_err(Error::StatsErr)?;
+    if days.is_empty() {
+        ctx.reply(format!("No songs played by `{}`.", player))
+            .await?;
+        return Ok(());
+    }
+
+    let mut desc = String::new();
+    for day in &days {
+        let rate = day
+            .guess_rate
+            .map(|r| format!("{:.1}%", r * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+        desc += &format!(
+            "**{}** - {} songs, {} guessed\n",
+            day.day, day.songs_played, rate
+        );
+    }
+    ctx.send(|cm| {
+        cm.embed(|ce| {
+            ce.title(format!("Songs played by {}", player))
+                .description(desc)
+                .footer(|f| f.text(format!("Days in {}", tz.name())))
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Set the timezone an AMQ player's stats are shown in
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn set_timezone(
+    ctx: Context<'_>,
+    #[description = "AMQ player name"] player: String,
+    #[description = "Timezone, e.g. Europe/London, or nothing to use UTC"] timezone: Option<String>,
+) -> Result<(), Error> {
+    let tz = timezone.as_deref().map(parse_timezone).transpose()?;
+    let found = ctx
+        .data()
+        .db
+        .set_user_timezone(&player, tz)
+        .await
+        .map_err(Error::StatsErr)?;
+    if !found {
+        return Err(Error::UnknownPlayer(player));
+    }
+    let tz = tz.map_or("UTC", |tz| tz.name());
+    ctx.reply(format!("Stats for `{}` are now shown in {}.", player, tz))
+        .await?;
+    Ok(())
+}
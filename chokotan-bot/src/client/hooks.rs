@@ -1,5 +1,6 @@
 use poise::{serenity_prelude as serenity, Event, FrameworkContext, FrameworkError};
 use serenity::{Activity, Channel, Context as SerenityContext, Message, OnlineStatus, Ready};
+use serenity::{Interaction, InteractionResponseType};
 
 use song_artist::Guess;
 
@@ -37,6 +38,21 @@ pub(super) async fn on_ready(
 
     log::info!("Registered {} slash commands globally.", commands.len());
 
+    // Offer to carry on a quiz that was running before the restart
+    match crate::quiz::SongArtistQuiz::interrupted() {
+        Ok(Some((channel_id, song_num))) => {
+            let msg = format!(
+                "A quiz was interrupted after song {}. Use `resume_quiz` to carry on.",
+                song_num
+            );
+            if let Err(e) = channel_id.say(ctx, msg).await {
+                log::warn!("Failed to offer to resume quiz: {}", e);
+            }
+        }
+        Ok(None) => (),
+        Err(e) => log::error!("Failed to read quiz snapshot: {}", e),
+    }
+
     let data = crate::Data::new(db);
 
     Ok(data)
@@ -103,6 +119,7 @@ pub(super) async fn on_event(
 ) -> Result<(), Error> {
     match event {
         Event::Message { new_message } => on_message(ctx, new_message, data).await?,
+        Event::InteractionCreate { interaction } => on_interaction(ctx, interaction, data).await?,
         Event::VoiceStateUpdate { old, new } => on_voice_state_change(ctx, old, new).await?,
         _ => (),
     }
@@ -118,35 +135,47 @@ async fn on_message(ctx: &SerenityContext, msg: &Message, data: &Data) -> Result
         return Ok(());
     }
 
-    if let Some(result) = quiz.handle_guess(&msg.content, now).ok().flatten() {
-        match result.song_guess {
-            Some(Guess::Incorrect(_g, p)) => msg
-                .reply(&ctx.http, format!("{:.1}%", p * 100.0))
+    if let Some(result) = quiz
+        .handle_guess(msg.author.id, &msg.content, now)
+        .ok()
+        .flatten()
+    {
+        if result.undone {
+            msg.react(&ctx.http, '↩')
                 .await
-                .map(|_| ()),
-            Some(Guess::Correct(_g, t)) => msg
-                .reply(&ctx.http, format!("✅ {:.1}s", t))
-                .await
-                .map(|_| ()),
-            None => Ok(()),
+                .map(|_| ())
+                .unwrap_or_else(|e| log::warn!("failed to react to message: {}", e));
+        }
+        // the others are marked since they're checked against the same message
+        let whole_guesses = [
+            (result.song_guess, ""),
+            (result.anime_guess, "Anime: "),
+            (result.song_type_guess, "Type: "),
+            (result.vintage_guess, "Vintage: "),
+        ];
+        for (g, prefix) in whole_guesses {
+            if let Some(g) = g {
+                msg.reply(&ctx.http, format!("{}{}", prefix, guess_text(&g)))
+                    .await
+                    .map(|_| ())
+                    .unwrap_or_else(|e| log::warn!("failed to send message: {}", e));
+            }
         }
-        .unwrap_or_else(|e| log::warn!("failed to send message: {}", e));
         let correct = result.num_correct_artists;
         let total = result.total_artists;
+        // say which part of the message each result is for if there were several
+        let show_fragment = result.artist_guesses.len() > 1;
         for g in result.artist_guesses {
-            match g {
-                Guess::Incorrect(_g, p) => {
-                    msg.reply(
-                        &ctx.http,
-                        format!("{:.1}% [{}/{}]", p * 100.0, correct, total),
-                    )
-                    .await
-                }
-                Guess::Correct(_g, t) => {
-                    msg.reply(&ctx.http, format!("✅ {:.1}s [{}/{}]", t, correct, total))
-                        .await
-                }
-            }
+            let prefix = if show_fragment {
+                format!("{}: ", g.fragment)
+            } else {
+                String::new()
+            };
+            msg.reply(
+                &ctx.http,
+                format!("{}{} [{}/{}]", prefix, guess_text(&g.guess), correct, total),
+            )
+            .await
             .map(|_| ())
             .unwrap_or_else(|e| log::warn!("failed to send message: {}", e));
         }
@@ -155,6 +184,48 @@ async fn on_message(ctx: &SerenityContext, msg: &Message, data: &Data) -> Result
     Ok(())
 }
 
+async fn on_interaction(
+    ctx: &SerenityContext,
+    interaction: &Interaction,
+    data: &Data,
+) -> Result<(), Error> {
+    let now = std::time::Instant::now();
+    let Interaction::MessageComponent(component) = interaction else {
+        return Ok(());
+    };
+    let Some((song_num, choice)) =
+        crate::client::messages::parse_choice_id(&component.data.custom_id)
+    else {
+        return Ok(());
+    };
+
+    // only the player sees what they picked, so they can't give it away
+    let text = match data
+        .quiz
+        .handle_choice(component.user.id, song_num, choice, now)
+    {
+        Ok(Some(g @ Guess::Correct(..))) => format!("Picked {}: {}", choice + 1, guess_text(&g)),
+        Ok(Some(_)) => format!("Picked {}: ❌", choice + 1),
+        Ok(None) => "You've already picked for this song, or it's over.".to_string(),
+        Err(e) => e.to_string(),
+    };
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(text).ephemeral(true))
+        })
+        .await?;
+    Ok(())
+}
+
+fn guess_text(guess: &Guess) -> String {
+    match guess {
+        Guess::Incorrect(_g, p) => format!("{:.1}%", p * 100.0),
+        Guess::Correct(_g, t) => format!("✅ {:.1}s", t),
+        Guess::Partial(_g, c, t) => format!("🟨 {:.0}% {:.1}s", c * 100.0, t),
+    }
+}
+
 async fn on_voice_state_change(
     ctx: &SerenityContext,
     old: &Option<serenity::VoiceState>,
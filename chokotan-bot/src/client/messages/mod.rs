@@ -2,7 +2,7 @@ mod play_song;
 mod sa_quiz;
 
 pub(crate) use play_song::PlaySongMessage;
-pub(crate) use sa_quiz::QuizSongMessage;
+pub(crate) use sa_quiz::{parse_choice_id, QuizSongMessage};
 
 mod colours {
     pub const PLAYING_COLOUR: u32 = 0x13F203;
@@ -80,6 +80,41 @@ impl<'a> PlaySongMessage<'a> {
         Ok(())
     }
 
+    // Show the tags read from the file, and upload the cover art if there is any
+    pub(crate) async fn set_metadata(
+        &mut self,
+        metadata: stream_song::SongMetadata,
+    ) -> Result<(), Error> {
+        let tags = [
+            ("Title", &metadata.title),
+            ("Artist", &metadata.artist),
+            ("Album", &metadata.album),
+        ];
+        self.reply
+            .edit_embed(|embed| {
+                for (name, value) in tags {
+                    if let Some(value) = value {
+                        embed.field(name, value, false);
+                    }
+                }
+                embed
+            })
+            .await?;
+
+        if let Some(cover) = metadata.cover_art {
+            let filename = format!("cover.{}", cover.extension());
+            let attachment = serenity::AttachmentType::Bytes {
+                data: Vec::from(cover.data).into(),
+                filename,
+            };
+            self.reply
+                .ctx
+                .send(|cr| cr.attachment(attachment))
+                .await?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn update_loaded(&mut self, time: f64) -> Result<(), Error> {
         self.data.loaded = Some(time);
         self.reply
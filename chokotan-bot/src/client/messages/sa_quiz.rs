@@ -1,6 +1,6 @@
 use poise::serenity_prelude as serenity;
+use serenity::{ButtonStyle, CreateEmbed, CreateMessage};
 use serenity::{ChannelId, Http, Message};
-use serenity::{CreateEmbed, CreateMessage};
 
 use crate::quiz::QuizSongData;
 use crate::Error;
@@ -8,6 +8,14 @@ use crate::Error;
 use super::text::*;
 use super::{colours::*, format_time, value_to_string};
 
+// Custom id of the button for picking a choice is `quiz_choice:<song number>:<index>`
+const CHOICE_ID_PREFIX: &str = "quiz_choice:";
+
+pub(crate) fn parse_choice_id(id: &str) -> Option<(i64, usize)> {
+    let (song_num, choice) = id.strip_prefix(CHOICE_ID_PREFIX)?.split_once(':')?;
+    Some((song_num.parse().ok()?, choice.parse().ok()?))
+}
+
 // We don't use the poise Context because quizzes are so long running
 //  that we want to be able to spawn the task to run the quiz
 //  and complete the quiz start command
@@ -49,6 +57,39 @@ impl<H: AsRef<Http>> QuizSongMessage<H> {
         Ok(())
     }
 
+    // Lists the options and adds a button to pick each one
+    pub(crate) async fn set_choices(
+        &mut self,
+        song_num: i64,
+        choices: &[song_artist::Choice],
+    ) -> Result<(), Error> {
+        if let Some(mut embed) = self.pop_embed() {
+            let list: Vec<_> = choices
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("**{}.** {}", i + 1, c))
+                .collect();
+            embed.field("Choices", list.join("\n"), false);
+            self.message
+                .edit(self.http.as_ref(), |em| {
+                    em.set_embed(embed).components(|c| {
+                        c.create_action_row(|row| {
+                            for i in 0..choices.len() {
+                                row.create_button(|b| {
+                                    b.custom_id(format!("{}{}:{}", CHOICE_ID_PREFIX, song_num, i))
+                                        .label(i + 1)
+                                        .style(ButtonStyle::Primary)
+                                });
+                            }
+                            row
+                        })
+                    })
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn set_error(
         &mut self,
         e: Error,
@@ -68,10 +109,27 @@ impl<H: AsRef<Http>> QuizSongMessage<H> {
         Ok(())
     }
 
-    pub(crate) async fn set_finished(mut self, song_data: QuizSongData) -> Result<(), Error> {
+    pub(crate) async fn set_finished(
+        mut self,
+        song_data: QuizSongData,
+        scores: &[song_artist::PlayerScore],
+        team_scores: &[song_artist::PlayerScore],
+    ) -> Result<(), Error> {
+        // picking is over
+        if !self.message.components.is_empty() {
+            self.message
+                .edit(self.http.as_ref(), |em| em.components(|c| c))
+                .await?;
+        }
         if let Some(mut embed) = self.pop_embed() {
             embed.colour(FINISHED_COLOUR).description("");
             set_song_data(&mut embed, song_data);
+            if !scores.is_empty() {
+                embed.field("Points", points_text(scores, true), false);
+            }
+            if !team_scores.is_empty() {
+                embed.field("Teams", points_text(team_scores, false), false);
+            }
             self.message
                 .channel_id
                 .send_message(self.http.as_ref(), |cm| {
@@ -120,6 +178,34 @@ fn quiz_song_message<'b>(
     }
 }
 
+// Longest text Discord allows in an embed field
+const FIELD_LENGTH: usize = 1024;
+
+// Each player's (or team's) points for the song and where they came from,
+//  for as many as fit in a field
+fn points_text(scores: &[song_artist::PlayerScore], players: bool) -> String {
+    let mut text = String::new();
+    for score in scores {
+        let parts: Vec<_> = score
+            .breakdown
+            .iter()
+            .map(|p| format!("{} {:+.1}", p.source, p.points))
+            .collect();
+        // players are stored by user id, teams by name
+        let name = if players {
+            format!("<@{}>", score.player)
+        } else {
+            format!("**{}**", score.player)
+        };
+        let line = format!("{} **{:.1}** ({})\n", name, score.points, parts.join(", "));
+        if text.len() + line.len() > FIELD_LENGTH {
+            break;
+        }
+        text += &line;
+    }
+    text
+}
+
 fn set_song_data(embed: &mut CreateEmbed, song_data: QuizSongData) {
     if let Some(info) = song_data.song_info {
         for crate::quiz::QuizInfoField { name, col, .. } in song_data.display_fields.iter() {
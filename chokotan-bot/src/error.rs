@@ -56,6 +56,21 @@ pub enum Error {
     QuizLoadConfigErr(#[from] crate::quiz::LoadConfigError),
     #[error("quiz parameter error: {0}")]
     QuizParamErr(#[from] crate::quiz::ParamError),
+    #[error("quiz snapshot error: {0}")]
+    QuizSnapshotErr(#[from] crate::quiz::SnapshotError),
+    #[error("No interrupted quiz to resume in this channel")]
+    NoQuizToResume,
+    #[error("{0}")]
+    TeamErr(song_artist::Error),
+    #[error("Can't explain: {0}")]
+    ExplainErr(song_artist::Error),
+
+    #[error("Failed to get stats from database: {0}")]
+    StatsErr(database::Error),
+    #[error("Unknown timezone: `{0}`")]
+    InvalidTimezone(String),
+    #[error("No AMQ player called `{0}`")]
+    UnknownPlayer(String),
 
     #[error("error decoding song: {0}")]
     DecodeSongError(stream_song::Error),
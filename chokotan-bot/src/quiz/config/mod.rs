@@ -42,6 +42,8 @@ pub enum Error {
     ReadFileError(io::Error, String),
     #[error("SQL query with no limit clause detected")]
     QueryWithNoLimit,
+    #[error("invalid scorer: {0}")]
+    InvalidScorer(song_artist::Error),
 }
 
 // TODO: tidy this up, return a better type
@@ -91,6 +93,18 @@ pub(crate) fn read_configs() -> Result<LoadedConfigs, Error> {
     })
 }
 
+// Reads the normalisation rules used for every quiz, if there are any
+pub(crate) fn read_rules() -> Result<Option<song_artist::Rules>, song_artist::Error> {
+    let data_dir = std::env::var("CHOKOTAN_DATA_PATH").unwrap_or_else(|_| String::from("."));
+    let path = format!("{}/chokotan/quiz/rules.yaml", &data_dir);
+    if !path::Path::new(&path).exists() {
+        return Ok(None);
+    }
+    let rules = song_artist::RuleSet::load(&path)?;
+    log::info!("Read {} normalisation rules.", rules.rules.len());
+    rules.compile().map(Some)
+}
+
 fn load_config(file: fs::File, query_dir: &str) -> Result<QuizConfig, LoadConfigError> {
     let reader = io::BufReader::new(file);
     let result = serde_yaml::from_reader::<_, QuizConfigYaml>(reader);
@@ -117,6 +131,20 @@ struct QuizConfigYaml {
     types: HashMap<Box<str>, database::ValueType>,
     // list of fields to display in the result
     fields: Arc<[QuizInfoField]>,
+    // other names to accept for artists, on top of those in the database
+    #[serde(default)]
+    artist_aliases: HashMap<String, Vec<String>>,
+    // how guesses are compared to the answers, e.g. `jaro-winkler`
+    scorer: Option<String>,
+    // what to guess, e.g. `{ song_name: false, artist: false, anime: true }`
+    //  the query needs to return an `anime_names` text array to guess the anime,
+    //  a `song_type` column (with an optional `type_number`) or a `type` string column
+    //  and a `vintage` string column to guess those
+    targets: Option<song_artist::GuessTargets>,
+    // number of past songs to keep, instead of `song_artist::DEFAULT_HISTORY_LEN`
+    history: Option<usize>,
+    // how points are scored, e.g. `{ rules: [{ rule: flat, points: 1 }] }`
+    scoring: Option<song_artist::ScoreSettings>,
 }
 
 impl QuizConfigYaml {
@@ -134,6 +162,12 @@ impl QuizConfigYaml {
             return Err(Error::QueryWithNoLimit);
         }
 
+        let scorer = self
+            .scorer
+            .map(|s| s.parse())
+            .transpose()
+            .map_err(Error::InvalidScorer)?;
+
         Ok(QuizConfig {
             name: self.name,
             description: self.description,
@@ -141,6 +175,11 @@ impl QuizConfigYaml {
             params: self.params,
             types: self.types,
             fields: self.fields,
+            artist_aliases: self.artist_aliases,
+            scorer,
+            targets: self.targets,
+            history: self.history,
+            scoring: self.scoring,
         })
     }
 }
@@ -159,6 +198,16 @@ pub(crate) struct QuizConfig {
     // list of fields to display in the result
     // TODO: validate each field actually exists as a column
     fields: Arc<[QuizInfoField]>,
+    // artist name, other accepted names
+    artist_aliases: HashMap<String, Vec<String>>,
+    // scorer to use instead of the default
+    scorer: Option<song_artist::ScorerKind>,
+    // what to guess instead of the default
+    targets: Option<song_artist::GuessTargets>,
+    // past songs to keep instead of the default
+    history: Option<usize>,
+    // scoring rules instead of the default
+    scoring: Option<song_artist::ScoreSettings>,
 }
 
 impl QuizConfig {
@@ -181,6 +230,26 @@ impl QuizConfig {
     pub(crate) fn fields(&self) -> Arc<[QuizInfoField]> {
         self.fields.clone()
     }
+
+    pub(crate) fn artist_aliases(&self) -> &HashMap<String, Vec<String>> {
+        &self.artist_aliases
+    }
+
+    pub(crate) fn scorer(&self) -> Option<song_artist::ScorerKind> {
+        self.scorer
+    }
+
+    pub(crate) fn targets(&self) -> Option<song_artist::GuessTargets> {
+        self.targets
+    }
+
+    pub(crate) fn history(&self) -> Option<usize> {
+        self.history
+    }
+
+    pub(crate) fn scoring(&self) -> Option<&song_artist::ScoreSettings> {
+        self.scoring.as_ref()
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -1,11 +1,11 @@
 use std::{
     collections::{HashMap, VecDeque},
     sync::{Arc, Mutex},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use poise::serenity_prelude as serenity;
-use serenity::ChannelId;
+use serenity::{ChannelId, UserId};
 
 use song_artist::GuessSettings;
 use stream_song::Message;
@@ -13,17 +13,23 @@ use tokio_util::sync::CancellationToken;
 
 mod config;
 mod settings;
+mod snapshot;
 
 use crate::Error;
 pub(crate) use config::{
     Error as LoadConfigError, ParamError, QuizConfig, QuizInfoField, QuizParameters,
 };
+pub(crate) use snapshot::Error as SnapshotError;
+use snapshot::QuizSnapshot;
 
 // TODO: make this something that handles all quizzes across servers
 #[derive(Clone)]
 pub(crate) struct SongArtistQuiz {
     inner: Arc<Mutex<QuizInner>>,
     db: database::Database,
+    // held while writing or removing the snapshot file, never together with `inner`
+    //  except to remove it when stopping
+    snapshot_file: Arc<Mutex<()>>,
 }
 
 struct QuizInner {
@@ -31,6 +37,8 @@ struct QuizInner {
     // settings
     settings: song_artist::GuessSettings,
     configs: HashMap<Box<str>, Arc<QuizConfig>>,
+    // kept between quizzes, players stay in their team until they leave
+    teams: song_artist::Teams,
     // other stuff - todo: these should go into the quiz state too
     channel_id: Option<ChannelId>,
     cancel_token: CancellationToken,
@@ -47,6 +55,23 @@ impl QuizInner {
 
             settings: song_artist::GuessSettings::default(),
             configs,
+            teams: song_artist::Teams::default(),
+        }
+    }
+
+    // Gives the running quiz the latest teams
+    fn update_teams(&self) {
+        if let QuizState::Started(ref quiz) = self.state {
+            quiz.quiz.set_teams(self.teams.clone());
+        }
+    }
+
+    // Keeps the current rules if the rules file can't be read
+    fn load_rules(&mut self) {
+        match config::read_rules() {
+            Ok(Some(rules)) => self.settings.set_rules(rules),
+            Ok(None) => self.settings.set_rules(Default::default()),
+            Err(e) => log::error!("Failed to read normalisation rules: {}", e),
         }
     }
 
@@ -75,20 +100,60 @@ struct Quiz {
     //
     config: Arc<QuizConfig>,
     params: QuizParameters,
+    // the parameters as given, kept for snapshots
+    raw_params: HashMap<String, String>,
 }
 
 type SongInfo = (database::SongInfo, Arc<[QuizInfoField]>);
 
+// Options offered when players pick the song from a list, including the answer
+const NUM_CHOICES: usize = 4;
+
+// How often the running quiz is saved, at most this much is lost to a restart
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
 impl Quiz {
-    fn new(config: Arc<QuizConfig>, params: QuizParameters, settings: GuessSettings) -> Self {
+    fn new(
+        config: Arc<QuizConfig>,
+        params: QuizParameters,
+        raw_params: HashMap<String, String>,
+        mut settings: GuessSettings,
+    ) -> Self {
+        for (artist, aliases) in config.artist_aliases() {
+            settings.add_artist_aliases(artist, aliases.iter().map(String::as_str));
+        }
+        if let Some(kind) = config.scorer() {
+            settings.set_scorer(kind);
+        }
+        if let Some(targets) = config.targets() {
+            settings.targets = targets;
+        }
+        if let Some(scoring) = config.scoring() {
+            settings.scoring = scoring.clone();
+        }
+        let quiz = song_artist::SongArtistQuiz::new_with_settings(settings);
+        if let Some(len) = config.history() {
+            quiz.set_history_len(len);
+        }
         Quiz {
             song_num: 0,
             song_info: VecDeque::new(),
             song_start_time: Instant::now(),
             song_token: CancellationToken::new(),
-            quiz: song_artist::SongArtistQuiz::new_with_settings(settings),
+            quiz,
             config,
             params,
+            raw_params,
+        }
+    }
+
+    fn snapshot(&self, channel_id: ChannelId) -> QuizSnapshot {
+        QuizSnapshot {
+            channel_id,
+            quiz_type: self.config.name().to_string(),
+            params: self.raw_params.clone(),
+            song_num: self.song_num,
+            quiz: self.quiz.snapshot(),
         }
     }
 }
@@ -119,9 +184,13 @@ impl SongArtistQuiz {
             }
         };
 
+        let mut inner = QuizInner::new(quiz_configs);
+        inner.load_rules();
+
         SongArtistQuiz {
-            inner: Arc::new(Mutex::new(QuizInner::new(quiz_configs))),
+            inner: Arc::new(Mutex::new(inner)),
             db,
+            snapshot_file: Arc::new(Mutex::new(())),
         }
     }
 
@@ -143,19 +212,66 @@ impl SongArtistQuiz {
             .ok_or(Error::InvalidQuizType(quiz_type))?
             .clone();
 
+        let raw_params = params.clone();
         let params = config.parse_params(params)?;
 
         match guard.state {
             QuizState::NotStarted => {
                 guard.cancel_token = CancellationToken::new();
                 guard.channel_id = Some(channel_id);
-                guard.state = QuizState::Started(Quiz::new(config, params, guard.settings.clone()));
+                let quiz = Quiz::new(config, params, raw_params, guard.settings.clone());
+                quiz.quiz.set_teams(guard.teams.clone());
+                guard.state = QuizState::Started(quiz);
             }
             QuizState::Started { .. } => return Err(Error::QuizAlreadyStarted),
         }
         Ok(())
     }
 
+    // Carries on the quiz that was interrupted in this channel,
+    //  returning the number of the last song played
+    pub(crate) fn resume(&self, channel_id: ChannelId) -> Result<i64, Error> {
+        let snapshot = match snapshot::load()? {
+            Some(s) if s.channel_id == channel_id => s,
+            _ => return Err(Error::NoQuizToResume),
+        };
+
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        if let QuizState::Started { .. } = guard.state {
+            return Err(Error::QuizAlreadyStarted);
+        }
+        log::debug!(
+            "Resuming quiz of type `{}` in channel `{}` after song {}",
+            snapshot.quiz_type,
+            channel_id,
+            snapshot.song_num
+        );
+        let config = guard
+            .configs
+            .get(snapshot.quiz_type.as_str())
+            .ok_or(Error::InvalidQuizType(snapshot.quiz_type))?
+            .clone();
+        let params = config.parse_params(snapshot.params.clone())?;
+
+        let mut quiz = Quiz::new(config, params, snapshot.params, guard.settings.clone());
+        quiz.quiz =
+            song_artist::SongArtistQuiz::from_snapshot(snapshot.quiz, guard.settings.clone())
+                .map_err(SnapshotError::RestoreErr)?;
+        quiz.song_num = snapshot.song_num;
+        // the teams are saved with the quiz
+        guard.teams = quiz.quiz.teams();
+
+        guard.cancel_token = CancellationToken::new();
+        guard.channel_id = Some(channel_id);
+        guard.state = QuizState::Started(quiz);
+        Ok(snapshot.song_num)
+    }
+
+    // The channel of the quiz that was interrupted by a restart, and the last song played
+    pub(crate) fn interrupted() -> Result<Option<(ChannelId, i64)>, Error> {
+        Ok(snapshot::load()?.map(|s| (s.channel_id, s.song_num)))
+    }
+
     pub(crate) fn stop(&self) -> Result<Option<QuizTask>, Error> {
         let mut guard = self.inner.lock().expect("poisoned mutex");
         let task;
@@ -168,9 +284,53 @@ impl SongArtistQuiz {
                 guard.state = QuizState::NotStarted;
             }
         }
+        // a stopped quiz isn't offered to resume
+        let _file = self.snapshot_file.lock().expect("poisoned mutex");
+        if let Err(e) = snapshot::remove() {
+            log::warn!("Failed to remove quiz snapshot: {}", e);
+        }
         Ok(task)
     }
 
+    // Saves a snapshot of the running quiz every `SNAPSHOT_INTERVAL` until it is stopped,
+    //  not being able to resume later shouldn't stop the quiz
+    pub(crate) async fn save_snapshots(self, token: CancellationToken) {
+        let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            let snapshot = {
+                let mut guard = self.inner.lock().expect("poisoned mutex");
+                let Some(channel_id) = guard.channel_id else {
+                    break;
+                };
+                match guard.get_quiz() {
+                    Ok(quiz) => quiz.snapshot(channel_id),
+                    Err(_) => break,
+                }
+            };
+
+            let file = self.snapshot_file.clone();
+            let token = token.clone();
+            let saved = tokio::task::spawn_blocking(move || {
+                let _file = file.lock().expect("poisoned mutex");
+                // a stopped quiz can't be saved again after it is removed
+                if token.is_cancelled() {
+                    return Ok(());
+                }
+                snapshot::save(&snapshot)
+            })
+            .await;
+            match saved {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => log::warn!("Failed to save quiz snapshot: {}", e),
+                Err(e) => log::warn!("Quiz snapshot task failed: {}", e),
+            }
+        }
+    }
+
     // TODO: pass in a cancel token?
     pub(crate) async fn load_next_song(
         &self,
@@ -192,6 +352,44 @@ impl SongArtistQuiz {
         let (source, mut loader_rx, cancel_token) =
             crate::audio::create_input(&url, sample).await?;
 
+        // Missing aliases shouldn't stop the quiz, they just make guessing harder
+        let artists = {
+            let mut guard = self.inner.lock().expect("poisoned mutex");
+            guard.get_quiz()?.quiz.split_artists(&song_info.artist)
+        };
+        let aliases = match self.db.get_artist_aliases(&artists).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("Failed to get artist aliases: {}", e);
+                Vec::new()
+            }
+        };
+
+        // Without wrong answers there is nothing to pick from, but the song still plays
+        let picking = {
+            let mut guard = self.inner.lock().expect("poisoned mutex");
+            guard.get_quiz()?.quiz.targets().choice
+        };
+        let distractors = if picking {
+            let count = NUM_CHOICES as i64 - 1;
+            match self
+                .db
+                .get_distractors(&song_info.song_name, &song_info.artist, count)
+                .await
+            {
+                Ok(v) => v
+                    .into_iter()
+                    .map(|(song_name, artist)| song_artist::Choice { song_name, artist })
+                    .collect(),
+                Err(e) => {
+                    log::warn!("Failed to get distractors: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
         let info = Some(song_info).into();
 
         // Set the song info for the next song
@@ -201,6 +399,14 @@ impl SongArtistQuiz {
             quiz.quiz
                 .set_info(quiz.song_num + 1, info)
                 .map_err(Error::SetSongInfo)?;
+            quiz.quiz
+                .set_artist_aliases(quiz.song_num + 1, aliases)
+                .map_err(Error::SetSongInfo)?;
+            if picking {
+                quiz.quiz
+                    .set_distractors(quiz.song_num + 1, distractors)
+                    .map_err(Error::SetSongInfo)?;
+            }
             log::debug!("Loading song {}: {}", quiz.song_num + 1, &url);
         }
 
@@ -217,6 +423,8 @@ impl SongArtistQuiz {
                             song_data.duration = Some(t.seconds as f64 + t.frac);
                         }
                         Message::Update(t) => if t.seconds >= 15 { break },
+                        // don't give away the answer from the file tags
+                        Message::Metadata(_) => (),
                         Message::DecodeError(e) => return Err(Error::DecodeSongError(e)),
                     }
                 }
@@ -267,7 +475,14 @@ impl SongArtistQuiz {
         quiz.song_num = song_num;
         quiz.song_start_time = Instant::now();
         quiz.quiz.set_song_number(quiz.song_num);
+        Ok(())
+    }
 
+    // The current song can be explained once it has finished
+    pub(crate) fn finish_song(&self) -> Result<(), Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        quiz.quiz.finish_song();
         Ok(())
     }
 
@@ -281,6 +496,7 @@ impl SongArtistQuiz {
 
     pub(crate) fn handle_guess(
         &self,
+        player: UserId,
         guess: &str,
         time: std::time::Instant,
     ) -> Result<Option<song_artist::GuessResult>, Error> {
@@ -289,7 +505,9 @@ impl SongArtistQuiz {
 
         let time = time - quiz.song_start_time;
 
-        let result = quiz.quiz.handle_guess(guess, time.as_secs_f32());
+        let result = quiz
+            .quiz
+            .handle_guess(&player.to_string(), guess, time.as_secs_f32());
 
         // TODO: do this outside cos the quiz result shows up before
         if quiz.quiz.correct() {
@@ -299,6 +517,118 @@ impl SongArtistQuiz {
         Ok(result)
     }
 
+    // Options to pick from for the current song, if players are picking
+    pub(crate) fn choices(&self) -> Result<Option<Vec<song_artist::Choice>>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        Ok(quiz.quiz.choices())
+    }
+
+    // Records a player picking an option for a song,
+    //  which only counts while that song is playing
+    pub(crate) fn handle_choice(
+        &self,
+        player: UserId,
+        song_num: i64,
+        choice: usize,
+        time: std::time::Instant,
+    ) -> Result<Option<song_artist::Guess>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        if song_num != quiz.song_num {
+            return Ok(None);
+        }
+
+        let time = time - quiz.song_start_time;
+
+        Ok(quiz
+            .quiz
+            .handle_choice(&player.to_string(), choice, time.as_secs_f32()))
+    }
+
+    // Explains how a guess scored against a finished song, by default the last one to finish.
+    // Returns the song number with the explanation
+    pub(crate) fn explain(
+        &self,
+        guess: &str,
+        song_num: Option<i64>,
+    ) -> Result<(i64, song_artist::GuessExplanation), Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        let song_num = song_num.unwrap_or_else(|| quiz.quiz.last_finished_song());
+        let explanation = quiz
+            .quiz
+            .explain(song_num, guess)
+            .map_err(Error::ExplainErr)?;
+        Ok((song_num, explanation))
+    }
+
+    // Next hint for the current song
+    pub(crate) fn hint(&self) -> Result<Option<song_artist::Hint>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        let time = quiz.song_start_time.elapsed();
+        Ok(quiz.quiz.hint(time.as_secs_f32()))
+    }
+
+    // Points for each player in the current song, with where they came from
+    pub(crate) fn song_scores(&self) -> Result<Vec<song_artist::PlayerScore>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        Ok(quiz.quiz.song_scores())
+    }
+
+    // Points for each team in the current song
+    pub(crate) fn team_song_scores(&self) -> Result<Vec<song_artist::PlayerScore>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        Ok(quiz.quiz.team_song_scores())
+    }
+
+    // Total points for each team in the current quiz
+    pub(crate) fn team_scoreboard(&self) -> Result<Vec<song_artist::PlayerScore>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        Ok(quiz.quiz.team_scoreboard())
+    }
+
+    pub(crate) fn create_team(&self, team: &str) -> Result<(), Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        guard.teams.create(team).map_err(Error::TeamErr)?;
+        guard.update_teams();
+        Ok(())
+    }
+
+    pub(crate) fn join_team(&self, player: UserId, team: &str) -> Result<(), Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        guard
+            .teams
+            .join(&player.to_string(), team)
+            .map_err(Error::TeamErr)?;
+        guard.update_teams();
+        Ok(())
+    }
+
+    // Returns the team the player left, if they were in one
+    pub(crate) fn leave_team(&self, player: UserId) -> Option<String> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let team = guard.teams.leave(&player.to_string());
+        guard.update_teams();
+        team
+    }
+
+    pub(crate) fn teams(&self) -> song_artist::Teams {
+        let guard = self.inner.lock().expect("poisoned mutex");
+        guard.teams.clone()
+    }
+
+    // Total points for each player in the current quiz
+    pub(crate) fn scoreboard(&self) -> Result<Vec<song_artist::PlayerScore>, Error> {
+        let mut guard = self.inner.lock().expect("poisoned mutex");
+        let quiz = guard.get_quiz()?;
+        Ok(quiz.quiz.scoreboard())
+    }
+
     pub(crate) fn channel_id(&self) -> Option<ChannelId> {
         let guard = self.inner.lock().expect("poisoned mutex");
         guard.channel_id.clone()
@@ -334,6 +664,7 @@ impl SongArtistQuiz {
         let num_configs = quiz_configs.len();
         let mut guard = self.inner.lock().expect("poisoned mutex");
         guard.configs = quiz_configs;
+        guard.load_rules();
         Ok(config::LoadedConfigsResult {
             num_configs,
             num_dupes: loaded.duplicates.len(),
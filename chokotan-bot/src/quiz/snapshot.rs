@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path;
+
+use poise::serenity_prelude as serenity;
+use serenity::ChannelId;
+
+// A quiz saved so that it can be carried on after a restart
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct QuizSnapshot {
+    pub channel_id: ChannelId,
+    // name of the quiz config and the parameters it was started with
+    pub quiz_type: String,
+    pub params: HashMap<String, String>,
+    pub song_num: i64,
+    pub quiz: song_artist::Snapshot,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to write quiz snapshot: {0}")]
+    WriteErr(io::Error),
+    #[error("failed to read quiz snapshot: {0}")]
+    ReadErr(io::Error),
+    #[error("failed to serialize quiz snapshot: {0}")]
+    SerializeErr(serde_yaml::Error),
+    #[error("failed to deserialize quiz snapshot: {0}")]
+    DeserializeErr(serde_yaml::Error),
+    #[error("failed to restore quiz: {0}")]
+    RestoreErr(song_artist::Error),
+}
+
+fn snapshot_path() -> String {
+    let data_dir = std::env::var("CHOKOTAN_DATA_PATH").unwrap_or_else(|_| String::from("."));
+    format!("{}/chokotan/quiz/snapshot.yaml", &data_dir)
+}
+
+// Written to a temporary file first so a crash can't leave half a snapshot
+pub(crate) fn save(snapshot: &QuizSnapshot) -> Result<(), Error> {
+    let path = snapshot_path();
+    if let Some(p) = AsRef::<path::Path>::as_ref(&path).parent() {
+        fs::create_dir_all(p).map_err(Error::WriteErr)?;
+    }
+    let tmp = format!("{}.tmp", &path);
+    let yaml = serde_yaml::to_string(snapshot).map_err(Error::SerializeErr)?;
+    fs::write(&tmp, yaml).map_err(Error::WriteErr)?;
+    fs::rename(&tmp, &path).map_err(Error::WriteErr)
+}
+
+pub(crate) fn load() -> Result<Option<QuizSnapshot>, Error> {
+    let file = match fs::File::open(snapshot_path()) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::ReadErr(e)),
+    };
+    serde_yaml::from_reader(io::BufReader::new(file))
+        .map(Some)
+        .map_err(Error::DeserializeErr)
+}
+
+pub(crate) fn remove() -> Result<(), Error> {
+    match fs::remove_file(snapshot_path()) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(Error::WriteErr(e)),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,54 @@
+// Issues, lists and revokes the API keys players submit song plays with
+//
+// DATABASE_URL=... cargo run -p database --example api_keys -- issue <player> [name]
+// DATABASE_URL=... cargo run -p database --example api_keys -- list <player>
+// DATABASE_URL=... cargo run -p database --example api_keys -- revoke <key id>
+
+const USAGE: &str = "usage: api_keys issue <player> [name] | list <player> | revoke <key id>";
+
+#[tokio::main]
+async fn main() {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let db = database::Database::new(url);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args[..] {
+        ["issue", player, ref name @ ..] => {
+            match db.issue_api_key(player, name.first().copied()).await {
+                Ok((id, key)) => {
+                    println!("issued key {} for {}: {}", id, player, key);
+                    println!("this is the only time the key is shown");
+                }
+                Err(e) => eprintln!("failed to issue key: {}", e),
+            }
+        }
+        ["list", player] => {
+            let keys = db.get_api_keys(player).await.expect("failed to get keys");
+            for key in keys {
+                let status = match (key.revoked_date, key.last_used_date) {
+                    (Some(date), _) => format!("revoked {}", date),
+                    (None, Some(date)) => format!("last used {}", date),
+                    (None, None) => "never used".to_string(),
+                };
+                println!(
+                    "{:>4} {}... {:<16} created {}, {}",
+                    key.id,
+                    key.prefix,
+                    key.name.as_deref().unwrap_or(""),
+                    key.created_date,
+                    status
+                );
+            }
+        }
+        ["revoke", id] => {
+            let id = id.parse().expect("key id should be a number");
+            if db.revoke_api_key(id).await.expect("failed to revoke key") {
+                println!("revoked key {}", id);
+            } else {
+                println!("no unrevoked key {}", id);
+            }
+        }
+        _ => eprintln!("{}", USAGE),
+    }
+}
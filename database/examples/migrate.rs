@@ -0,0 +1,18 @@
+// Applies the migrations to a database, then again to check there's nothing left to apply.
+// Meant for a throwaway database, e.g.
+//
+// createdb amq_scratch
+// DATABASE_URL=postgres://localhost/amq_scratch cargo run -p database --example migrate
+
+#[tokio::main]
+async fn main() {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let db = database::Database::new(url);
+
+    let applied = db.migrate().await.expect("failed to migrate");
+    println!("applied migrations: {:?}", applied);
+
+    let again = db.migrate().await.expect("failed to migrate again");
+    assert!(again.is_empty(), "migrations applied twice: {:?}", again);
+    println!("schema is up to date");
+}
@@ -0,0 +1,65 @@
+-- Tables as they were before migrations were added,
+--  `IF NOT EXISTS` so that this is a no-op on existing databases
+
+CREATE TABLE IF NOT EXISTS amq_anime (
+    ann_id INT PRIMARY KEY,
+    romaji TEXT,
+    english TEXT,
+    mal_id INT,
+    anilist_id INT,
+    kitsu_id INT,
+    type TEXT,
+    score REAL,
+    vintage TEXT,
+    created_date TIMESTAMP NOT NULL,
+    modified_date TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS amq_anime_names (
+    ann_id INT NOT NULL REFERENCES amq_anime (ann_id),
+    name TEXT NOT NULL,
+    modified_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (ann_id, name)
+);
+
+-- tag_type is 1 for genres and 2 for tags
+CREATE TABLE IF NOT EXISTS song_anime_tags (
+    amq_anime_id INT NOT NULL REFERENCES amq_anime (ann_id),
+    tag_type INT NOT NULL,
+    tag TEXT NOT NULL,
+    modified_date TIMESTAMP NOT NULL,
+    PRIMARY KEY (amq_anime_id, tag_type, tag)
+);
+
+CREATE TABLE IF NOT EXISTS amq_songs (
+    id SERIAL PRIMARY KEY,
+    songname TEXT NOT NULL,
+    artist TEXT NOT NULL,
+    anime_id INT NOT NULL REFERENCES amq_anime (ann_id),
+    type TEXT NOT NULL,
+    mp3 TEXT,
+    video TEXT,
+    video_length REAL,
+    difficulty REAL,
+    created_date TIMESTAMP NOT NULL,
+    modified_date TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS amq_songs_anime_id ON amq_songs (anime_id);
+CREATE INDEX IF NOT EXISTS amq_songs_mp3 ON amq_songs (mp3);
+CREATE INDEX IF NOT EXISTS amq_songs_video ON amq_songs (video);
+
+CREATE TABLE IF NOT EXISTS users (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+-- correct_guesses holds the last 16 guesses, most recent first
+CREATE TABLE IF NOT EXISTS song_plays (
+    amq_song_id INT NOT NULL REFERENCES amq_songs (id),
+    player INT NOT NULL REFERENCES users (id),
+    times_played INT NOT NULL,
+    correct_guesses BIT VARYING(16) NOT NULL,
+    guess_rate REAL,
+    last_played TIMESTAMP NOT NULL,
+    PRIMARY KEY (amq_song_id, player)
+);
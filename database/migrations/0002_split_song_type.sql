@@ -0,0 +1,18 @@
+-- Splits the combined type, e.g. "Opening 2", into the type and its number
+ALTER TABLE amq_songs
+    ADD COLUMN song_type TEXT,
+    ADD COLUMN type_number INT;
+
+UPDATE amq_songs
+SET
+    song_type = regexp_replace(type, ' [0-9]+$', ''),
+    type_number = substring(type FROM ' ([0-9]+)$')::INT;
+
+ALTER TABLE amq_songs ALTER COLUMN song_type SET NOT NULL;
+CREATE INDEX amq_songs_song_type ON amq_songs (song_type, type_number);
+
+-- kept for queries written against the combined type
+ALTER TABLE amq_songs DROP COLUMN type;
+ALTER TABLE amq_songs
+    ADD COLUMN type TEXT
+    GENERATED ALWAYS AS (song_type || COALESCE(' ' || type_number::TEXT, '')) STORED;
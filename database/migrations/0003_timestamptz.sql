@@ -0,0 +1,25 @@
+-- Dates were stored as local time in Sydney without a timezone,
+--  they are now stored with it and shown in whatever timezone is wanted
+ALTER TABLE amq_anime
+    ALTER COLUMN created_date TYPE TIMESTAMPTZ USING created_date AT TIME ZONE 'Australia/Sydney',
+    ALTER COLUMN modified_date TYPE TIMESTAMPTZ USING modified_date AT TIME ZONE 'Australia/Sydney';
+
+ALTER TABLE amq_anime_names
+    ALTER COLUMN modified_date TYPE TIMESTAMPTZ USING modified_date AT TIME ZONE 'Australia/Sydney';
+
+ALTER TABLE song_anime_tags
+    ALTER COLUMN modified_date TYPE TIMESTAMPTZ USING modified_date AT TIME ZONE 'Australia/Sydney';
+
+ALTER TABLE amq_songs
+    ALTER COLUMN created_date TYPE TIMESTAMPTZ USING created_date AT TIME ZONE 'Australia/Sydney',
+    ALTER COLUMN modified_date TYPE TIMESTAMPTZ USING modified_date AT TIME ZONE 'Australia/Sydney';
+
+ALTER TABLE song_plays
+    ALTER COLUMN last_played TYPE TIMESTAMPTZ USING last_played AT TIME ZONE 'Australia/Sydney';
+
+-- set with now(), so in the server's timezone
+ALTER TABLE schema_migrations
+    ALTER COLUMN applied_date TYPE TIMESTAMPTZ;
+
+-- IANA name, e.g. "Europe/London", to show the user's stats in (UTC if not set)
+ALTER TABLE users ADD COLUMN timezone TEXT;
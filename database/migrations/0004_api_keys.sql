@@ -0,0 +1,13 @@
+-- Keys for submitting song plays as a user, only the SHA-256 hash of each key is kept
+CREATE TABLE api_keys (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users (id),
+    key_hash BYTEA NOT NULL UNIQUE,
+    -- start of the key, to tell keys apart without storing them
+    prefix TEXT NOT NULL,
+    name TEXT,
+    created_date TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_date TIMESTAMPTZ,
+    revoked_date TIMESTAMPTZ
+);
+CREATE INDEX api_keys_user_id ON api_keys (user_id);
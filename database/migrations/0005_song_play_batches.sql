@@ -0,0 +1,9 @@
+-- Plays already taken from each batch a user has sent,
+--  so a batch sent again after a failure doesn't count them twice
+CREATE TABLE song_play_batches (
+    user_id INT NOT NULL REFERENCES users (id),
+    batch_id TEXT NOT NULL,
+    item INT NOT NULL,
+    created_date TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, batch_id, item)
+);
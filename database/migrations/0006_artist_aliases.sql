@@ -0,0 +1,7 @@
+-- Other names an artist is credited or known as, e.g. the character a voice actor sings as
+CREATE TABLE amq_artist_aliases (
+    artist TEXT NOT NULL,
+    alias TEXT NOT NULL,
+    PRIMARY KEY (artist, alias)
+);
+CREATE INDEX amq_artist_aliases_lower_artist ON amq_artist_aliases (lower(artist));
@@ -0,0 +1,4 @@
+-- Plays are told apart by an id the sender gives each one rather than by
+--  their place in the batch, so sending only the failed plays again still works
+ALTER TABLE song_play_batches RENAME COLUMN item TO play_id;
+ALTER TABLE song_play_batches ALTER COLUMN play_id TYPE TEXT USING play_id::TEXT;
@@ -0,0 +1,4 @@
+-- For finding songs that share the artist, season or tags of a song
+CREATE INDEX amq_songs_lower_artist ON amq_songs (lower(artist));
+CREATE INDEX amq_anime_vintage ON amq_anime (vintage);
+CREATE INDEX song_anime_tags_tag ON song_anime_tags (tag_type, tag);
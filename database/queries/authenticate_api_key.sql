@@ -0,0 +1,7 @@
+UPDATE api_keys k
+SET last_used_date = now()
+FROM users u
+WHERE k.user_id = u.id
+AND k.key_hash = $1
+AND k.revoked_date IS NULL
+RETURNING u.name;
@@ -0,0 +1,8 @@
+-- Held until the end of the transaction so only one migration runs at a time
+SELECT pg_advisory_xact_lock(hashtext('schema_migrations'));
+
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INT PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_date TIMESTAMPTZ NOT NULL DEFAULT now()
+);
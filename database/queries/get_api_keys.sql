@@ -0,0 +1,6 @@
+SELECT k.id, k.prefix, k.name, k.created_date, k.last_used_date, k.revoked_date
+FROM api_keys k
+JOIN users u
+ON k.user_id = u.id
+WHERE u.name = $1
+ORDER BY k.created_date;
@@ -0,0 +1,3 @@
+SELECT artist, alias
+FROM amq_artist_aliases
+WHERE lower(artist) = ANY($1::TEXT[]);
@@ -0,0 +1,12 @@
+-- Songs last played by a player on each day in a timezone, most recent first
+SELECT
+  (p.last_played AT TIME ZONE $2)::DATE AS day,
+  COUNT(*),
+  AVG(p.guess_rate)
+FROM song_plays p
+JOIN users u
+ON p.player = u.id
+WHERE u.name = $1
+GROUP BY day
+ORDER BY day DESC
+LIMIT $3;
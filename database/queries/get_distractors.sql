@@ -0,0 +1,76 @@
+-- Plausible wrong answers for a song, most alike first:
+--  songs by the same artist, from anime of the same season,
+--  from anime with the same tags or with names starting the same way.
+-- Only a limited number of songs sharing the artist, season or tags are looked at,
+--  so that the whole song table isn't scored and sorted
+WITH answer AS (
+  SELECT s.anime_id, a.vintage
+  FROM amq_songs s
+  LEFT JOIN amq_anime a
+  ON s.anime_id = a.ann_id
+  WHERE lower(s.artist) = lower($2) AND s.songname = $1
+  LIMIT 1
+),
+shared_tags AS (
+  SELECT t.amq_anime_id, LEAST(COUNT(*), 3) AS num_tags
+  FROM song_anime_tags t
+  JOIN song_anime_tags answer_tags
+  ON t.tag_type = answer_tags.tag_type AND t.tag = answer_tags.tag
+  JOIN answer
+  ON answer_tags.amq_anime_id = answer.anime_id
+  GROUP BY t.amq_anime_id
+  ORDER BY num_tags DESC, RANDOM()
+  LIMIT 100
+),
+pool AS (
+  (
+    SELECT s.id
+    FROM amq_songs s
+    WHERE lower(s.artist) = lower($2)
+    ORDER BY RANDOM()
+    LIMIT 100
+  )
+  UNION
+  (
+    SELECT s.id
+    FROM amq_songs s
+    JOIN amq_anime a
+    ON s.anime_id = a.ann_id
+    JOIN answer
+    ON a.vintage = answer.vintage
+    ORDER BY RANDOM()
+    LIMIT 100
+  )
+  UNION
+  (
+    SELECT s.id
+    FROM amq_songs s
+    JOIN shared_tags st
+    ON s.anime_id = st.amq_anime_id
+    ORDER BY RANDOM()
+    LIMIT 100
+  )
+),
+candidates AS (
+  SELECT DISTINCT ON (lower(s.songname)) s.songname, s.artist,
+    CASE WHEN lower(s.artist) = lower($2) THEN 4 ELSE 0 END
+    + CASE WHEN a.vintage = answer.vintage THEN 2 ELSE 0 END
+    + COALESCE(st.num_tags, 0)
+    + CASE WHEN split_part(lower(s.songname), ' ', 1) = split_part(lower($1), ' ', 1) THEN 1 ELSE 0 END
+    AS likeness
+  FROM pool
+  JOIN amq_songs s
+  ON pool.id = s.id
+  LEFT JOIN amq_anime a
+  ON s.anime_id = a.ann_id
+  LEFT JOIN answer
+  ON true
+  LEFT JOIN shared_tags st
+  ON s.anime_id = st.amq_anime_id
+  WHERE lower(s.songname) <> lower($1)
+  ORDER BY lower(s.songname), likeness DESC
+)
+SELECT songname, artist
+FROM candidates
+ORDER BY likeness DESC, RANDOM()
+LIMIT $3;
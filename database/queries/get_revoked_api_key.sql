@@ -0,0 +1,6 @@
+SELECT u.name
+FROM api_keys k
+JOIN users u
+ON k.user_id = u.id
+WHERE k.key_hash = $1
+AND k.revoked_date IS NOT NULL;
@@ -1,5 +1,12 @@
-SELECT songname, artist
-FROM amq_songs
+SELECT songname, artist,
+  ARRAY(
+    SELECT name
+    FROM amq_anime_names an
+    WHERE an.ann_id = s.anime_id
+  ) AS anime_names,
+  song_type,
+  type_number
+FROM amq_songs s
 WHERE mp3 = $1 OR video = $2
 ORDER BY modified_date DESC
 LIMIT 1;
@@ -0,0 +1,3 @@
+SELECT timezone
+FROM users
+WHERE name = $1;
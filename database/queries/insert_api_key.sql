@@ -0,0 +1,5 @@
+INSERT INTO api_keys (user_id, key_hash, prefix, name)
+SELECT id, $2, $3, $4
+FROM users
+WHERE name = $1
+RETURNING id;
@@ -0,0 +1,6 @@
+-- Nothing is returned if the play was already taken,
+--  a retry running at the same time waits for the first to finish
+INSERT INTO song_play_batches (user_id, batch_id, play_id)
+VALUES ($1, $2, $3)
+ON CONFLICT DO NOTHING
+RETURNING play_id;
@@ -3,15 +3,16 @@ SET
     songname = $1,
     artist = $2,
     anime_id = $3,
-    type = $4,
-    mp3 = COALESCE($5, mp3),
-    video = COALESCE($6, video),
-    video_length = COALESCE($7, video_length),
-    difficulty = $8,
-    modified_date = $9
+    song_type = $4,
+    type_number = $5,
+    mp3 = COALESCE($6, mp3),
+    video = COALESCE($7, video),
+    video_length = COALESCE($8, video_length),
+    difficulty = $9,
+    modified_date = $10
 WHERE anime_id = $3
 AND (
-    (video = $6 AND (mp3 = $5 OR mp3 IS NULL))
-    OR (songname = $1 AND artist = $2 AND type = $4)
+    (video = $7 AND (mp3 = $6 OR mp3 IS NULL))
+    OR (songname = $1 AND artist = $2 AND song_type = $4 AND type_number IS NOT DISTINCT FROM $5)
 )
 RETURNING id
@@ -0,0 +1,4 @@
+UPDATE api_keys
+SET revoked_date = now()
+WHERE id = $1
+AND revoked_date IS NULL;
@@ -0,0 +1,3 @@
+UPDATE users
+SET timezone = $2
+WHERE name = $1;
@@ -0,0 +1,189 @@
+use crate::Error::QueryError;
+use crate::{Database, Error};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use tokio_postgres::types::ToSql;
+
+// Keys are this prefix then 64 hex digits
+const KEY_PREFIX: &str = "amq_";
+const KEY_HEX_LEN: usize = 64;
+// Characters of the key kept to tell keys apart
+const SHOWN_LEN: usize = KEY_PREFIX.len() + 8;
+
+// The user a key belongs to
+pub struct ApiKeyUser {
+    pub player_name: String,
+    pub revoked: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct ApiKeyInfo {
+    pub id: i32,
+    // start of the key
+    pub prefix: String,
+    pub name: Option<String>,
+    pub created_date: DateTime<Utc>,
+    pub last_used_date: Option<DateTime<Utc>>,
+    pub revoked_date: Option<DateTime<Utc>>,
+}
+
+// Keys are random so a plain hash is enough, there's nothing to guess from it
+fn hash_key(key: &str) -> Vec<u8> {
+    Sha256::digest(key.as_bytes()).to_vec()
+}
+
+// Whether the text could be a key, so made up ones don't need looking up
+fn is_key(key: &str) -> bool {
+    key.strip_prefix(KEY_PREFIX)
+        .is_some_and(|hex| hex.len() == KEY_HEX_LEN && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", KEY_PREFIX, hex)
+}
+
+impl Database {
+    // Returns the id of the new key and the key itself,
+    //  which is the only time it can be seen
+    pub async fn issue_api_key(
+        &self,
+        player: &str,
+        name: Option<&str>,
+    ) -> Result<(i32, String), Error> {
+        let client = self.client().await?;
+
+        let key = generate_key();
+        let statement = prepare_statement!(client, "insert_api_key.sql", "issue api key")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&player, &hash_key(&key), &&key[..SHOWN_LEN], &name];
+        let row = client
+            .query_opt(&statement, params)
+            .await
+            .map_err(|e| QueryError("issue api key", e))?
+            .ok_or_else(|| Error::NoSuchUser(player.to_string()))?;
+        Ok((row.get(0), key))
+    }
+
+    // Returns false if there is no such key or it was already revoked
+    pub async fn revoke_api_key(&self, key_id: i32) -> Result<bool, Error> {
+        let client = self.client().await?;
+
+        let statement = prepare_statement!(client, "revoke_api_key.sql", "revoke api key")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&key_id];
+        let num_rows = client
+            .execute(&statement, params)
+            .await
+            .map_err(|e| QueryError("revoke api key", e))?;
+        Ok(num_rows > 0)
+    }
+
+    // Returns who the key belongs to, `None` if it isn't a key.
+    // Revoked keys are returned too, so they can be told apart from made up ones,
+    //  but only keys that aren't revoked are marked as used
+    pub async fn authenticate_api_key(&self, key: &str) -> Result<Option<ApiKeyUser>, Error> {
+        if !is_key(key) {
+            return Ok(None);
+        }
+        let client = self.client().await?;
+
+        let statement =
+            prepare_statement!(client, "authenticate_api_key.sql", "authenticate api key")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&hash_key(key)];
+        let row = client
+            .query_opt(&statement, params)
+            .await
+            .map_err(|e| QueryError("authenticate api key", e))?;
+        if let Some(row) = row {
+            return Ok(Some(ApiKeyUser {
+                player_name: row.get(0),
+                revoked: false,
+            }));
+        }
+
+        let statement =
+            prepare_statement!(client, "get_revoked_api_key.sql", "get revoked api key")?;
+        let row = client
+            .query_opt(&statement, params)
+            .await
+            .map_err(|e| QueryError("get revoked api key", e))?;
+        let user = row.map(|r| ApiKeyUser {
+            player_name: r.get(0),
+            revoked: true,
+        });
+        Ok(user)
+    }
+
+    pub async fn get_api_keys(&self, player: &str) -> Result<Vec<ApiKeyInfo>, Error> {
+        let client = self.client().await?;
+
+        let statement = prepare_statement!(client, "get_api_keys.sql", "get api keys")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&player];
+        let rows = client
+            .query(&statement, params)
+            .await
+            .map_err(|e| QueryError("get api keys", e))?;
+        let results = rows
+            .into_iter()
+            .map(|r| ApiKeyInfo {
+                id: r.get(0),
+                prefix: r.get(1),
+                name: r.get(2),
+                created_date: r.get(3),
+                last_used_date: r.get(4),
+                revoked_date: r.get(5),
+            })
+            .collect();
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "amq_0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn well_formed_keys() {
+        assert!(is_key(KEY));
+        for _ in 0..10 {
+            assert!(is_key(&generate_key()));
+        }
+    }
+
+    #[test]
+    fn wrong_prefix() {
+        assert!(!is_key(&KEY.replace("amq_", "amp_")));
+        assert!(!is_key(&KEY.replace("amq_", "AMQ_")));
+        assert!(!is_key(&KEY["amq_".len()..]));
+    }
+
+    #[test]
+    fn wrong_length() {
+        assert!(!is_key("amq_"));
+        assert!(!is_key(&KEY[..KEY.len() - 1]));
+        assert!(!is_key(&format!("{}0", KEY)));
+    }
+
+    #[test]
+    fn non_hex() {
+        assert!(!is_key(&KEY.replace('a', "g")));
+        assert!(!is_key(&KEY.replace('0', " ")));
+        // same length in bytes, but not all hex digits
+        assert!(!is_key(&format!("{}é", &KEY[..KEY.len() - 2])));
+    }
+
+    #[test]
+    fn hash_round_trip() {
+        let hash: String = hash_key(KEY).iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hash,
+            "da27796302ca1b13452e1d3df763e9f1650c19fc32ba906180358c7d45eade35"
+        );
+        assert_eq!(hash_key(KEY).len(), 32);
+        assert_ne!(hash_key(KEY), hash_key(&generate_key()));
+    }
+}
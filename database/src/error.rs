@@ -11,6 +11,10 @@ pub enum Error {
     PrepareStatement(&'static str, tokio_postgres::Error),
     #[error("query failed for {0}: {1}")]
     QueryError(&'static str, tokio_postgres::Error),
+    #[error("database migration {0} failed: {1}")]
+    MigrationError(&'static str, tokio_postgres::Error),
+    #[error("no user called `{0}`")]
+    NoSuchUser(String),
     #[error("postgres error: {0}")]
     PostgresError(#[from] tokio_postgres::Error),
     // #[error("deserialise error: {0}")]
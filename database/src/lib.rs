@@ -23,15 +23,20 @@ macro_rules! prepare_statement {
     );
 }
 
+mod api_key;
 mod database;
 mod error;
+mod migrate;
 mod song;
 mod stats;
 mod types;
 
+pub use api_key::{ApiKeyInfo, ApiKeyUser};
 pub use database::Database;
 pub use error::Error;
-pub use song::{SearchQuery, SongData, SongInfo};
+pub use song::{BatchItemResult, SearchQuery, SongData, SongDataBatch, SongInfo};
+pub use stats::DailyPlays;
 pub use types::{SqlValue, Value, ValueType};
 
+pub use chrono_tz::Tz;
 pub use tokio_postgres::types::ToSql;
@@ -0,0 +1,91 @@
+use crate::Error::QueryError;
+use crate::{Database, Error};
+use tokio_postgres::types::ToSql;
+
+macro_rules! migration {
+    ($version:literal, $file:literal) => {
+        Migration {
+            version: $version,
+            name: $file,
+            sql: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/", $file)),
+        }
+    };
+}
+
+struct Migration {
+    version: i32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+// Applied in order, never edit or remove one that has been released
+const MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_initial.sql"),
+    migration!(2, "0002_split_song_type.sql"),
+    migration!(3, "0003_timestamptz.sql"),
+    migration!(4, "0004_api_keys.sql"),
+    migration!(5, "0005_song_play_batches.sql"),
+    migration!(6, "0006_artist_aliases.sql"),
+    migration!(7, "0007_song_play_ids.sql"),
+    migration!(8, "0008_distractor_indexes.sql"),
+];
+
+impl Database {
+    // Brings the schema up to date, returning the versions that were applied.
+    // Everything is applied in one transaction, so a failed migration leaves
+    //  the schema as it was
+    pub async fn migrate(&self) -> Result<Vec<i32>, Error> {
+        let mut client = self.client().await?;
+        let transaction = client.transaction().await?;
+
+        transaction
+            .batch_execute(include_query!("create_schema_migrations.sql"))
+            .await
+            .map_err(|e| QueryError("create schema migrations", e))?;
+        let statement = prepare_statement!(
+            transaction,
+            "get_schema_migrations.sql",
+            "get schema migrations"
+        )?;
+        let applied: Vec<i32> = transaction
+            .query(&statement, &[])
+            .await
+            .map_err(|e| QueryError("get schema migrations", e))?
+            .into_iter()
+            .map(|r| r.get(0))
+            .collect();
+        if let Some(latest) = applied.iter().max() {
+            if *latest > MIGRATIONS.len() as i32 {
+                log::warn!(
+                    "Database schema version {} is newer than the latest known migration",
+                    latest
+                );
+            }
+        }
+
+        let statement = prepare_statement!(
+            transaction,
+            "insert_schema_migration.sql",
+            "insert schema migration"
+        )?;
+        let mut versions = Vec::new();
+        for migration in MIGRATIONS {
+            if applied.contains(&migration.version) {
+                continue;
+            }
+            log::info!("Applying database migration {}", migration.name);
+            transaction
+                .batch_execute(migration.sql)
+                .await
+                .map_err(|e| Error::MigrationError(migration.name, e))?;
+            let params: &[&(dyn ToSql + Sync)] = &[&migration.version, &migration.name];
+            transaction
+                .execute(&statement, params)
+                .await
+                .map_err(|e| QueryError("insert schema migration", e))?;
+            versions.push(migration.version);
+        }
+        transaction.commit().await?;
+        Ok(versions)
+    }
+}
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use crate::types;
 use crate::Error::QueryError;
 use crate::{Database, Error};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Transaction;
 use tokio_postgres::types::ToSql;
 use tokio_postgres::Row;
 
@@ -12,14 +14,41 @@ pub struct SongData {
     pub correct: Option<bool>,
     pub player_name: Option<String>,
     pub video_length: Option<f64>,
+    // chosen by the sender for each play in a batch and kept the same when it's sent again
+    #[serde(default)]
+    pub play_id: Option<String>,
     #[serde(flatten)]
     pub song_info: amq_types::ResultsSongInfo,
 }
 
+// many song plays from one game
+#[derive(serde::Deserialize)]
+pub struct SongDataBatch {
+    // chosen by the sender and kept the same when sending the batch again
+    pub batch_id: String,
+    pub songs: Vec<SongData>,
+}
+
+// what happened to each song play in a batch
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchItemResult {
+    Ok,
+    // already taken from an earlier send of the batch
+    Duplicate,
+    Error { message: String },
+}
+
 #[derive(Clone)]
 pub struct SongInfo {
     pub song_name: String,
     pub artist: String,
+    // every stored name for the anime, only read by quiz queries
+    //  that return an `anime_names` text array
+    pub anime_names: Vec<String>,
+    // e.g. "Opening" and 2, read from the `song_type` and `type_number` columns
+    pub song_type: Option<String>,
+    pub type_number: Option<i32>,
     // map of column name to value
     pub fields: HashMap<Box<str>, types::Value>,
 }
@@ -45,6 +74,9 @@ impl SongInfo {
         SongInfo {
             song_name: r.get(0),
             artist: r.get(1),
+            anime_names: r.get(2),
+            song_type: r.get(3),
+            type_number: r.get(4),
             fields,
         }
     }
@@ -54,6 +86,9 @@ impl SongInfo {
         let mut info = SongInfo {
             song_name: String::new(),
             artist: String::new(),
+            anime_names: Vec::new(),
+            song_type: None,
+            type_number: None,
             fields: HashMap::new(),
         };
         for col in r.columns() {
@@ -62,6 +97,12 @@ impl SongInfo {
                 info.song_name = r.try_get(colname).map_err(Error::TypeError)?;
             } else if colname == "artist" {
                 info.artist = r.try_get(colname).map_err(Error::TypeError)?;
+            } else if colname == "anime_names" {
+                info.anime_names = r.try_get(colname).map_err(Error::TypeError)?;
+            } else if colname == "song_type" {
+                info.song_type = r.try_get(colname).map_err(Error::TypeError)?;
+            } else if colname == "type_number" {
+                info.type_number = r.try_get(colname).map_err(Error::TypeError)?;
             } else if colname == "url" {
                 let value = Value::String(r.try_get(colname).map_err(Error::TypeError)?);
                 info.fields.insert(colname.into(), value);
@@ -110,185 +151,257 @@ pub struct SearchResult {
     difficulty: Option<f32>,
 }
 
-impl Database {
-    // Updates the anime data (including name and tags),
-    //  then the song data and finally the song play data
-    //  for an AMQ song.
-    // This function can be called without any of the the player song guess data
-    //  so if it is being called from an external source, it should be checked
-    //  to exist before calling this
-    pub async fn update_song_data(&self, mut data: SongData) -> Result<(), Error> {
-        // my timezone
-        let date = chrono::offset::Utc::now().with_timezone(&chrono_tz::Australia::Sydney);
-        let date = date.naive_local(); // remove timezone info to give to postgres
+// Writes everything `update_song_data` does within a transaction
+async fn write_song_data(
+    transaction: &Transaction<'_>,
+    mut data: SongData,
+    date: DateTime<Utc>,
+) -> Result<(), Error> {
+    // Insert/Update anime data
+    let params: &[&(dyn ToSql + Sync)] = &[
+        // ann_id,
+        &(data.song_info.ann_id as i32),
+        // romaji, english,
+        &data.song_info.anime_names.romaji,
+        &data.song_info.anime_names.english,
+        // mal_id, anilist_id, kitsu_id,
+        &data.song_info.site_ids.mal_id.map(|x| x as i32),
+        &data.song_info.site_ids.ani_list_id.map(|x| x as i32),
+        &data.song_info.site_ids.kitsu_id.map(|x| x as i32),
+        // type, score, vintage,
+        &data.song_info.anime_type,
+        &(data.song_info.anime_score as f32),
+        &data.song_info.vintage,
+        // created_date,
+        &date,
+    ];
+    let statement = prepare_statement!(transaction, "pg_insert_anime.sql", "update anime")?;
+    transaction
+        .execute(&statement, params)
+        .await
+        .map_err(|e| QueryError("update anime", e))?;
 
-        let mut client = self.client().await?;
-        let transaction = client.transaction().await?;
+    // Insert/Update anime tags (1 for genre, 2 for tags)
+    let tags_iter = data
+        .song_info
+        .anime_genre
+        .iter()
+        .map(|g| (1, g))
+        .chain(data.song_info.anime_tags.iter().map(|t| (2, t)));
 
-        // Insert/Update anime data
+    let statement =
+        prepare_statement!(transaction, "pg_insert_anime_tags.sql", "update anime tags")?;
+    for (tag_type, tag) in tags_iter {
         let params: &[&(dyn ToSql + Sync)] = &[
-            // ann_id,
+            // amq_anime_id,
             &(data.song_info.ann_id as i32),
-            // romaji, english,
-            &data.song_info.anime_names.romaji,
-            &data.song_info.anime_names.english,
-            // mal_id, anilist_id, kitsu_id,
-            &data.song_info.site_ids.mal_id.map(|x| x as i32),
-            &data.song_info.site_ids.ani_list_id.map(|x| x as i32),
-            &data.song_info.site_ids.kitsu_id.map(|x| x as i32),
-            // type, score, vintage,
-            &data.song_info.anime_type,
-            &(data.song_info.anime_score as f32),
-            &data.song_info.vintage,
-            // created_date,
+            // tag_type, tag,
+            &tag_type,
+            tag,
+            // modified_date
             &date,
         ];
-        let statement = prepare_statement!(transaction, "pg_insert_anime.sql", "update anime")?;
         transaction
             .execute(&statement, params)
             .await
-            .map_err(|e| QueryError("update anime", e))?;
+            .map_err(|e| QueryError("update anime tags", e))?;
+    }
+    // Insert/Update anime names
+    let names_iter = data
+        .song_info
+        .alt_anime_names
+        .iter()
+        .chain(std::iter::once(&data.song_info.anime_names.romaji))
+        .chain(std::iter::once(&data.song_info.anime_names.english));
 
-        // Insert/Update anime tags (1 for genre, 2 for tags)
-        let tags_iter = data
-            .song_info
-            .anime_genre
-            .iter()
-            .map(|g| (1, g))
-            .chain(data.song_info.anime_tags.iter().map(|t| (2, t)));
-
-        let statement =
-            prepare_statement!(transaction, "pg_insert_anime_tags.sql", "update anime tags")?;
-        for (tag_type, tag) in tags_iter {
-            let params: &[&(dyn ToSql + Sync)] = &[
-                // amq_anime_id,
-                &(data.song_info.ann_id as i32),
-                // tag_type, tag,
-                &tag_type,
-                tag,
-                // modified_date
-                &date,
-            ];
-            transaction
-                .execute(&statement, params)
-                .await
-                .map_err(|e| QueryError("update anime tags", e))?;
-        }
-        // Insert/Update anime names
-        let names_iter = data
-            .song_info
-            .alt_anime_names
-            .iter()
-            .chain(std::iter::once(&data.song_info.anime_names.romaji))
-            .chain(std::iter::once(&data.song_info.anime_names.english));
+    let statement = prepare_statement!(
+        transaction,
+        "pg_insert_anime_names.sql",
+        "update anime names"
+    )?;
+    for name in names_iter {
+        let params: &[&(dyn ToSql + Sync)] = &[
+            // ann_id,
+            &(data.song_info.ann_id as i32),
+            // name
+            name,
+            // modified_date
+            &date,
+        ];
+        transaction
+            .execute(&statement, params)
+            .await
+            .map_err(|e| QueryError("update anime names", e))?;
+    }
+    // Insert/Update song data
+    let song_type = data.song_info.ty.to_string();
+    let links = amq_types::get_links(data.song_info.url_map.take());
+    let params: &[&(dyn ToSql + Sync)] = &[
+        // songname, artist,
+        &data.song_info.song_name,
+        &data.song_info.artist,
+        // anime_id,
+        &(data.song_info.ann_id as i32),
+        // song_type, type_number,
+        &song_type,
+        &data.song_info.type_number.map(|x| x as i32),
+        // mp3, video,
+        &links.mp3,
+        &links.video,
+        // video_length,
+        &data.video_length.map(|x| x as f32),
+        // difficulty,
+        &data.song_info.anime_difficulty.map(|x| x as f32),
+        // created_date,
+        &date,
+    ];
 
+    let statement = prepare_statement!(transaction, "pg_update_song.sql", "update song data")?;
+    let res = transaction
+        .query_opt(&statement, params)
+        .await
+        .map_err(|e| QueryError("update song data", e))?;
+    let song_id: i32 = if let Some(row) = res {
+        row.get(0)
+    } else {
+        let statement = prepare_statement!(transaction, "pg_insert_song.sql", "insert song data")?;
+        // we use the same params here as the update
+        let row = transaction
+            .query_one(&statement, params)
+            .await
+            .map_err(|e| QueryError("insert song data", e))?;
+        row.get(0)
+    };
+    // Insert/Update song plays
+    // only do this if player name is set
+    if let Some(ref player_name) = data.player_name {
+        // ~~get the player id - could cache this?~~
+        // player id get in query
+        let params: &[&(dyn ToSql + Sync)] = &[
+            // song_id, name, correct, date
+            &song_id,
+            player_name,
+            &data.correct,
+            &date,
+        ];
+        // TODO: handle failure here (e.g. if player name invalid)
         let statement = prepare_statement!(
             transaction,
-            "pg_insert_anime_names.sql",
-            "update anime names"
+            "pg_insert_song_plays.sql",
+            "update song plays",
+            INT4,
+            TEXT,
+            BOOL,
+            TIMESTAMPTZ,
         )?;
-        for name in names_iter {
-            let params: &[&(dyn ToSql + Sync)] = &[
-                // ann_id,
-                &(data.song_info.ann_id as i32),
-                // name
-                name,
-                // modified_date
-                &date,
-            ];
+        let row = transaction
+            .query_one(&statement, params)
+            .await
+            .map_err(|e| QueryError("update song plays", e))?;
+        // couldn't figure out a good way to update the guess rate in the same query
+        // but just update it if we had a guess
+        if data.correct.is_some() {
+            let player_id: i32 = row.get(0);
+            let params: &[&(dyn ToSql + Sync)] = &[&song_id, &player_id];
+            let statement = prepare_statement!(
+                transaction,
+                "pg_update_guess_rate.sql",
+                "update song guess rate"
+            )?;
             transaction
                 .execute(&statement, params)
                 .await
-                .map_err(|e| QueryError("update anime names", e))?;
+                .map_err(|e| QueryError("update song guess rate", e))?;
         }
-        // Insert/Update song data
-        // TODO: change db schema to split type and type number
-        let song_type = if let Some(num) = data.song_info.type_number {
-            format!("{} {}", &data.song_info.ty, num)
-        } else {
-            data.song_info.ty.to_string()
-        };
-        let links = amq_types::get_links(data.song_info.url_map.take());
-        let params: &[&(dyn ToSql + Sync)] = &[
-            // songname, artist,
-            &data.song_info.song_name,
-            &data.song_info.artist,
-            // anime_id,
-            &(data.song_info.ann_id as i32),
-            // type,
-            &song_type,
-            // mp3, video,
-            &links.mp3,
-            &links.video,
-            // video_length,
-            &data.video_length.map(|x| x as f32),
-            // difficulty,
-            &data.song_info.anime_difficulty.map(|x| x as f32),
-            // created_date,
-            &date,
-        ];
+    }
+    Ok(())
+}
+
+impl Database {
+    // Updates the anime data (including name and tags),
+    //  then the song data and finally the song play data
+    //  for an AMQ song.
+    // This function can be called without any of the the player song guess data
+    //  so if it is being called from an external source, it should be checked
+    //  to exist before calling this
+    pub async fn update_song_data(&self, data: SongData) -> Result<(), Error> {
+        let date = chrono::offset::Utc::now();
 
-        let statement = prepare_statement!(transaction, "pg_update_song.sql", "update song data")?;
-        let res = transaction
+        let mut client = self.client().await?;
+        let transaction = client.transaction().await?;
+        write_song_data(&transaction, data, date).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    // Updates the data for every song play in the batch as the player, in one transaction.
+    // A play that fails is rolled back on its own and doesn't stop the others,
+    //  plays from the batch that were already taken are skipped
+    pub async fn update_song_data_batch(
+        &self,
+        player_name: &str,
+        batch: SongDataBatch,
+    ) -> Result<Vec<BatchItemResult>, Error> {
+        let date = chrono::offset::Utc::now();
+
+        let mut client = self.client().await?;
+        let mut transaction = client.transaction().await?;
+
+        let statement = prepare_statement!(transaction, "get_user_id.sql", "get user id")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&player_name];
+        let user_id: i32 = transaction
             .query_opt(&statement, params)
             .await
-            .map_err(|e| QueryError("update song data", e))?;
-        let song_id: i32 = if let Some(row) = res {
-            row.get(0)
-        } else {
-            let statement =
-                prepare_statement!(transaction, "pg_insert_song.sql", "insert song data")?;
-            // we use the same params here as the update
-            let row = transaction
-                .query_one(&statement, params)
-                .await
-                .map_err(|e| QueryError("insert song data", e))?;
-            row.get(0)
-        };
-        // Insert/Update song plays
-        // only do this if player name is set
-        if let Some(ref player_name) = data.player_name {
-            // ~~get the player id - could cache this?~~
-            // player id get in query
-            let params: &[&(dyn ToSql + Sync)] = &[
-                // song_id, name, correct, date
-                &song_id,
-                player_name,
-                &data.correct,
-                &date,
-            ];
-            // TODO: handle failure here (e.g. if player name invalid)
-            let statement = prepare_statement!(
-                transaction,
-                "pg_insert_song_plays.sql",
-                "update song plays",
-                INT4,
-                TEXT,
-                BOOL,
-                TIMESTAMP,
-            )?;
-            let row = transaction
-                .query_one(&statement, params)
-                .await
-                .map_err(|e| QueryError("update song plays", e))?;
-            // couldn't figure out a good way to update the guess rate in the same query
-            // but just update it if we had a guess
-            if data.correct.is_some() {
-                let player_id: i32 = row.get(0);
-                let params: &[&(dyn ToSql + Sync)] = &[&song_id, &player_id];
-                let statement = prepare_statement!(
-                    transaction,
-                    "pg_update_guess_rate.sql",
-                    "update song guess rate"
-                )?;
-                transaction
-                    .execute(&statement, params)
+            .map_err(|e| QueryError("get user id", e))?
+            .ok_or_else(|| Error::NoSuchUser(player_name.to_string()))?
+            .get(0);
+
+        let statement = prepare_statement!(
+            transaction,
+            "insert_song_play_batch_item.sql",
+            "insert batch item"
+        )?;
+        let mut results = Vec::with_capacity(batch.songs.len());
+        for mut data in batch.songs {
+            let play_id = match data.play_id.take() {
+                Some(play_id) if !play_id.is_empty() => play_id,
+                _ => {
+                    results.push(BatchItemResult::Error {
+                        message: "no play_id provided".to_string(),
+                    });
+                    continue;
+                }
+            };
+            data.player_name = Some(player_name.to_string());
+            let savepoint = transaction.savepoint("song_data").await?;
+            let result = async {
+                let params: &[&(dyn ToSql + Sync)] = &[&user_id, &batch.batch_id, &play_id];
+                let taken = savepoint
+                    .query_opt(&statement, params)
                     .await
-                    .map_err(|e| QueryError("update song guess rate", e))?;
+                    .map_err(|e| QueryError("insert batch item", e))?;
+                if taken.is_none() {
+                    return Ok(BatchItemResult::Duplicate);
+                }
+                write_song_data(&savepoint, data, date).await?;
+                Ok::<_, Error>(BatchItemResult::Ok)
+            }
+            .await;
+            match result {
+                Ok(r) => {
+                    savepoint.commit().await?;
+                    results.push(r);
+                }
+                Err(e) => {
+                    savepoint.rollback().await?;
+                    results.push(BatchItemResult::Error {
+                        message: e.to_string(),
+                    });
+                }
             }
         }
         transaction.commit().await?;
-        Ok(())
+        Ok(results)
     }
 
     // Returns the song information given the AMQ Catbox links used
@@ -309,6 +422,45 @@ impl Database {
         Ok(info)
     }
 
+    // Returns (artist, alias) for any aliases of each of the credited artists,
+    //  e.g. the character name a voice actor sings as
+    pub async fn get_artist_aliases(
+        &self,
+        artists: &[String],
+    ) -> Result<Vec<(String, String)>, Error> {
+        let client = self.client().await?;
+
+        let artists: Vec<String> = artists.iter().map(|a| a.trim().to_lowercase()).collect();
+        let statement = prepare_statement!(client, "get_artist_aliases.sql", "get artist aliases")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&artists];
+        let rows = client
+            .query(&statement, params)
+            .await
+            .map_err(|e| QueryError("get artist aliases", e))?;
+        let results = rows.into_iter().map(|r| (r.get(0), r.get(1))).collect();
+        Ok(results)
+    }
+
+    // Returns (song name, artist) for up to `count` other songs that could be
+    //  mistaken for the song, to offer as wrong answers
+    pub async fn get_distractors(
+        &self,
+        song_name: &str,
+        artist: &str,
+        count: i64,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let client = self.client().await?;
+
+        let statement = prepare_statement!(client, "get_distractors.sql", "get distractors")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&song_name, &artist, &count];
+        let rows = client
+            .query(&statement, params)
+            .await
+            .map_err(|e| QueryError("get distractors", e))?;
+        let results = rows.into_iter().map(|r| (r.get(0), r.get(1))).collect();
+        Ok(results)
+    }
+
     pub async fn search_songs(&self, search: SearchQuery) -> Result<Vec<SearchResult>, Error> {
         let client = self.client().await?;
 
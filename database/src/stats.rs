@@ -1,7 +1,17 @@
+use crate::Error::QueryError;
 use crate::{Database, Error};
+use chrono_tz::Tz;
 use tokio_postgres::types::ToSql;
 use tokio_postgres::Row;
 
+// Songs a player last played on a day
+#[derive(serde::Serialize)]
+pub struct DailyPlays {
+    pub day: chrono::NaiveDate,
+    pub songs_played: i64,
+    pub guess_rate: Option<f64>,
+}
+
 impl Database {
     pub async fn get_stats<T>(
         &self,
@@ -17,4 +27,66 @@ impl Database {
         let result = rows.into_iter().map(map).collect();
         Ok(result)
     }
+
+    // Returns the timezone the player wants their stats shown in,
+    //  `None` if it isn't set or there is no such player
+    pub async fn get_user_timezone(&self, player: &str) -> Result<Option<Tz>, Error> {
+        let client = self.client().await?;
+
+        let statement = prepare_statement!(client, "get_user_timezone.sql", "get user timezone")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&player];
+        let row = client
+            .query_opt(&statement, params)
+            .await
+            .map_err(|e| QueryError("get user timezone", e))?;
+        let name: Option<String> = row.and_then(|r| r.get(0));
+        let tz = name.and_then(|name| match name.parse() {
+            Ok(tz) => Some(tz),
+            Err(e) => {
+                log::warn!("Invalid timezone for {}: {}", player, e);
+                None
+            }
+        });
+        Ok(tz)
+    }
+
+    // Returns false if there is no such player
+    pub async fn set_user_timezone(&self, player: &str, tz: Option<Tz>) -> Result<bool, Error> {
+        let client = self.client().await?;
+
+        let statement = prepare_statement!(client, "set_user_timezone.sql", "set user timezone")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&player, &tz.map(|tz| tz.name())];
+        let num_rows = client
+            .execute(&statement, params)
+            .await
+            .map_err(|e| QueryError("set user timezone", e))?;
+        Ok(num_rows > 0)
+    }
+
+    // Songs last played on each of the player's last `days` days playing,
+    //  with the days starting at midnight in the timezone
+    pub async fn get_daily_plays(
+        &self,
+        player: &str,
+        tz: Tz,
+        days: i64,
+    ) -> Result<Vec<DailyPlays>, Error> {
+        let client = self.client().await?;
+
+        let statement = prepare_statement!(client, "get_daily_plays.sql", "get daily plays")?;
+        let params: &[&(dyn ToSql + Sync)] = &[&player, &tz.name(), &days];
+        let rows = client
+            .query(&statement, params)
+            .await
+            .map_err(|e| QueryError("get daily plays", e))?;
+        let results = rows
+            .into_iter()
+            .map(|r| DailyPlays {
+                day: r.get(0),
+                songs_played: r.get(1),
+                guess_rate: r.get(2),
+            })
+            .collect();
+        Ok(results)
+    }
 }
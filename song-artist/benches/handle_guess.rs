@@ -0,0 +1,125 @@
+// Guesses from a busy chat: a second of 1,000 messages from 200 players,
+//  mostly chatter and near misses with a few right answers
+//
+// cargo bench --bench handle_guess
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use song_artist::{GuessSettings, ScorerKind, SongArtistQuiz, SongInfo};
+
+const MESSAGES_PER_SECOND: usize = 1000;
+const PLAYERS: usize = 200;
+
+static CHATTER: &[&str] = &[
+    "lol",
+    "what is this",
+    "i know this one",
+    "no idea",
+    "this slaps",
+    "pog",
+    "wasn't this in a movie too? can't remember the name of it",
+    "いい曲",
+    "ｗｗｗ",
+    "skip",
+];
+
+static GUESSES: &[&str] = &[
+    "hacking to the gate",
+    "hacking to the gates",
+    "hacking the gate",
+    "hacking",
+    "gate",
+    "itou kanako",
+    "kanako ito",
+    "kanako itou, steins gate",
+    "steins;gate",
+    "sky clad no kansokusha",
+    "fripside",
+    "only my railgun",
+];
+
+fn song() -> SongInfo {
+    SongInfo::Info {
+        song_name: "Hacking to the Gate".to_string(),
+        artist: "Kanako Itou".to_string(),
+        anime_names: vec![
+            "Steins;Gate".to_string(),
+            "シュタインズ・ゲート".to_string(),
+        ],
+        song_type: Some("Opening 1".to_string()),
+        vintage: Some("Spring 2011".to_string()),
+        distractors: Vec::new(),
+        artist_aliases: Vec::new(),
+    }
+}
+
+// (player, message, seconds into the song)
+fn chat(seed: u64) -> Vec<(String, String, f32)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..MESSAGES_PER_SECOND)
+        .map(|i| {
+            let player = format!("{}", rng.gen_range(0..PLAYERS));
+            let text = if rng.gen_bool(0.3) {
+                CHATTER.choose(&mut rng).unwrap().to_string()
+            } else {
+                let mut guess: Vec<char> = GUESSES.choose(&mut rng).unwrap().chars().collect();
+                // a typo now and then
+                if rng.gen_bool(0.3) && guess.len() > 1 {
+                    let at = rng.gen_range(0..guess.len() - 1);
+                    guess.swap(at, at + 1);
+                }
+                guess.into_iter().collect()
+            };
+            let time = i as f32 / MESSAGES_PER_SECOND as f32;
+            (player, text, time)
+        })
+        .collect()
+}
+
+fn quiz(settings: GuessSettings) -> SongArtistQuiz {
+    let quiz = SongArtistQuiz::new_with_settings(settings);
+    quiz.set_info(1, song()).expect("failed to set song info");
+    quiz.set_song_number(1);
+    quiz
+}
+
+fn bench_chat(c: &mut Criterion) {
+    let messages = chat(0);
+    let mut group = c.benchmark_group("busy_chat");
+    group.throughput(Throughput::Elements(MESSAGES_PER_SECOND as u64));
+    for kind in ScorerKind::ALL {
+        let mut settings = GuessSettings::default();
+        settings.set_scorer(kind);
+        settings.targets.anime = true;
+        group.bench_function(kind.to_string(), |b| {
+            b.iter_batched(
+                || quiz(settings.clone()),
+                |quiz| {
+                    for (player, text, time) in &messages {
+                        criterion::black_box(quiz.handle_guess(player, text, *time));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// A single long message against a short answer, the case the length bound skips
+fn bench_hopeless(c: &mut Criterion) {
+    let text = CHATTER[6];
+    c.bench_function("hopeless_guess", |b| {
+        b.iter_batched(
+            || quiz(GuessSettings::default()),
+            |quiz| criterion::black_box(quiz.handle_guess("0", text, 1.0)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_chat, bench_hopeless);
+criterion_main!(benches);
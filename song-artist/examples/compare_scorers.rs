@@ -0,0 +1,117 @@
+// Compares how well each scorer separates guesses that should be accepted
+//  from those that shouldn't, using a corpus of guesses from past quizzes
+//
+// cargo run --example compare_scorers -- [corpus.tsv] [rules.yaml]
+
+use song_artist::{GuessSettings, RuleSet, Rules, ScorerKind};
+
+struct Entry {
+    artist: bool,
+    answer: String,
+    guess: String,
+    accept: bool,
+}
+
+fn read_corpus(path: &str) -> Vec<Entry> {
+    let text = std::fs::read_to_string(path).expect("failed to read corpus");
+    text.lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let cols: Vec<_> = l.split('\t').collect();
+            if cols.len() != 4 {
+                panic!("expected 4 columns: {}", l);
+            }
+            Entry {
+                artist: cols[0] == "artist",
+                answer: cols[1].to_string(),
+                guess: cols[2].to_string(),
+                accept: cols[3] == "accept",
+            }
+        })
+        .collect()
+}
+
+// Fraction of entries judged correctly when accepting anything above the threshold
+fn accuracy(scores: &[(f32, bool)], threshold: f32) -> f32 {
+    let correct = scores
+        .iter()
+        .filter(|(score, accept)| (*score > threshold) == *accept)
+        .count();
+    correct as f32 / scores.len() as f32
+}
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| {
+        format!(
+            "{}/examples/data/guess_corpus.tsv",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    });
+    let corpus = read_corpus(&path);
+    println!("{} guesses from {}", corpus.len(), path);
+
+    let rules = match std::env::args().nth(2) {
+        Some(path) => {
+            let rules = RuleSet::load(&path).expect("failed to load rules");
+            println!("{} rules from {}", rules.rules.len(), path);
+            rules.compile().expect("invalid rules")
+        }
+        None => Rules::default(),
+    };
+    println!();
+
+    println!(
+        "{:<20} {:>6} {:>9} {:>9} {:>9} {:>9}",
+        "scorer", "spaces", "default", "best", "threshold", "gap"
+    );
+    for kind in ScorerKind::ALL {
+        for space_sensitive in [false, true] {
+            let mut settings = GuessSettings::default();
+            settings.set_scorer(kind);
+            settings.space_sensitive = space_sensitive;
+            settings.set_rules(rules.clone());
+
+            let scores: Vec<(f32, bool)> = corpus
+                .iter()
+                .map(|e| {
+                    let score = if e.artist {
+                        settings.score_artist(&e.guess, &e.answer)
+                    } else {
+                        settings.score_song_name(&e.guess, &e.answer)
+                    };
+                    (score, e.accept)
+                })
+                .collect();
+
+            // the threshold just below each score is the only place accuracy can change
+            let (best_threshold, best) = scores
+                .iter()
+                .map(|(s, _)| s - 1e-4)
+                .map(|t| (t, accuracy(&scores, t)))
+                .fold((0.0, 0.0), |a, b| if b.1 > a.1 { b } else { a });
+
+            // how far apart the worst accepted and rejected guesses are,
+            //  negative if they overlap
+            let min_accept = scores
+                .iter()
+                .filter(|(_, a)| *a)
+                .map(|(s, _)| *s)
+                .fold(1.0, f32::min);
+            let max_reject = scores
+                .iter()
+                .filter(|(_, a)| !*a)
+                .map(|(s, _)| *s)
+                .fold(0.0, f32::max);
+
+            println!(
+                "{:<20} {:>6} {:>8.1}% {:>8.1}% {:>8.1}% {:>+8.1}%",
+                kind.to_string(),
+                space_sensitive,
+                accuracy(&scores, settings.answer_threshold) * 100.0,
+                best * 100.0,
+                best_threshold * 100.0,
+                (min_accept - max_reject) * 100.0,
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+// Replays the chat from a recorded quiz under two sets of guess settings
+//  and shows which messages would have been accepted or displayed differently,
+//  to check a change to the settings against real guesses before using it
+//
+// cargo run --example replay_guesses -- [session.yaml] [settings.yaml] [baseline.yaml]
+//
+// The baseline defaults to the default settings.
+// Paths to rules in a settings file are relative to that file
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use song_artist::{Guess, GuessResult, GuessSettings, GuessTargets, RuleSet, ScorerKind};
+use song_artist::{SongArtistQuiz, SongInfo};
+
+#[derive(serde::Deserialize)]
+struct Session {
+    songs: Vec<Song>,
+}
+
+#[derive(serde::Deserialize)]
+struct Song {
+    song_name: String,
+    artist: String,
+    #[serde(default)]
+    anime_names: Vec<String>,
+    song_type: Option<String>,
+    vintage: Option<String>,
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(serde::Deserialize)]
+struct ChatMessage {
+    // seconds since the song started
+    time: f32,
+    player: String,
+    text: String,
+}
+
+// Changes from the default settings, anything left out is kept
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    answer_threshold: Option<f32>,
+    display_threshold: Option<f32>,
+    space_sensitive: Option<bool>,
+    case_sensitive: Option<bool>,
+    punc_sensitive: Option<bool>,
+    transliterate: Option<bool>,
+    scorer: Option<ScorerKind>,
+    targets: Option<GuessTargets>,
+    rules: Option<String>,
+}
+
+fn read_yaml<T: serde::de::DeserializeOwned>(path: &str) -> T {
+    let file =
+        std::fs::File::open(path).unwrap_or_else(|e| panic!("failed to open {}: {}", path, e));
+    serde_yaml::from_reader(file).unwrap_or_else(|e| panic!("failed to read {}: {}", path, e))
+}
+
+fn read_settings(path: Option<&str>) -> GuessSettings {
+    let Some(path) = path else {
+        return GuessSettings::default();
+    };
+    let file: SettingsFile = read_yaml(path);
+    let mut settings = GuessSettings::default();
+    if let Some(v) = file.answer_threshold {
+        settings.answer_threshold = v;
+    }
+    if let Some(v) = file.display_threshold {
+        settings.display_threshold = v;
+    }
+    if let Some(v) = file.space_sensitive {
+        settings.space_sensitive = v;
+    }
+    if let Some(v) = file.case_sensitive {
+        settings.case_sensitive = v;
+    }
+    if let Some(v) = file.punc_sensitive {
+        settings.punc_sensitive = v;
+    }
+    if let Some(v) = file.transliterate {
+        settings.transliterate = v;
+    }
+    if let Some(kind) = file.scorer {
+        settings.set_scorer(kind);
+    }
+    if let Some(targets) = file.targets {
+        settings.targets = targets;
+    }
+    if let Some(rules) = file.rules {
+        let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+        let rules = dir.join(rules);
+        let rules = RuleSet::load(&rules).expect("failed to load rules");
+        settings.set_rules(rules.compile().expect("invalid rules"));
+    }
+    settings
+}
+
+// How a message changed what is shown for one of the answers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Hidden,
+    Shown,
+    Partial,
+    Correct,
+}
+
+impl From<&Guess> for Mark {
+    fn from(guess: &Guess) -> Self {
+        match guess {
+            Guess::Incorrect(..) => Mark::Shown,
+            Guess::Partial(..) => Mark::Partial,
+            Guess::Correct(..) => Mark::Correct,
+        }
+    }
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Mark::Hidden => "hidden",
+            Mark::Shown => "shown",
+            Mark::Partial => "partial",
+            Mark::Correct => "correct",
+        };
+        f.write_str(s)
+    }
+}
+
+// The answers a message changed, and how
+fn outcome(result: Option<&GuessResult>) -> HashMap<String, Mark> {
+    let mut marks = HashMap::new();
+    let Some(result) = result else {
+        return marks;
+    };
+    let guesses = [
+        ("song name", &result.song_guess),
+        ("anime", &result.anime_guess),
+        ("song type", &result.song_type_guess),
+        ("vintage", &result.vintage_guess),
+    ];
+    for (name, guess) in guesses {
+        if let Some(guess) = guess {
+            marks.insert(name.to_string(), Mark::from(guess));
+        }
+    }
+    for artist in &result.artist_guesses {
+        let name = format!("artist {}", artist.index + 1);
+        marks.insert(name, Mark::from(&artist.guess));
+    }
+    marks
+}
+
+#[derive(Default)]
+struct Totals {
+    accepted: usize,
+    displayed: usize,
+    songs_answered: usize,
+}
+
+// Outcome of every message in each song
+fn replay(session: &Session, settings: GuessSettings) -> (Vec<Vec<HashMap<String, Mark>>>, Totals) {
+    let quiz = SongArtistQuiz::new_with_settings(settings);
+    let mut totals = Totals::default();
+    let mut songs = Vec::new();
+    for (i, song) in session.songs.iter().enumerate() {
+        let song_number = i as i64 + 1;
+        let info = SongInfo::Info {
+            song_name: song.song_name.clone(),
+            artist: song.artist.clone(),
+            anime_names: song.anime_names.clone(),
+            song_type: song.song_type.clone(),
+            vintage: song.vintage.clone(),
+            distractors: Vec::new(),
+            artist_aliases: Vec::new(),
+        };
+        quiz.set_info(song_number, info)
+            .expect("failed to set song info");
+        quiz.set_song_number(song_number);
+
+        let mut outcomes = Vec::new();
+        for msg in &song.messages {
+            let marks = outcome(quiz.handle_guess(&msg.player, &msg.text, msg.time).as_ref());
+            if marks.values().any(|&m| m == Mark::Correct) {
+                totals.accepted += 1;
+            }
+            if !marks.is_empty() {
+                totals.displayed += 1;
+            }
+            outcomes.push(marks);
+        }
+        if quiz.correct() {
+            totals.songs_answered += 1;
+        }
+        songs.push(outcomes);
+    }
+    (songs, totals)
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| format!("{}/examples/data/session.yaml", env!("CARGO_MANIFEST_DIR")));
+    let session: Session = read_yaml(&path);
+    let num_messages: usize = session.songs.iter().map(|s| s.messages.len()).sum();
+    println!(
+        "{} songs and {} messages from {}",
+        session.songs.len(),
+        num_messages,
+        path
+    );
+
+    let candidate_path = std::env::args().nth(2).unwrap_or_else(|| {
+        format!(
+            "{}/examples/data/replay_settings.yaml",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    });
+    let baseline_path = std::env::args().nth(3);
+    let (baseline, baseline_totals) = replay(&session, read_settings(baseline_path.as_deref()));
+    let (candidate, candidate_totals) = replay(&session, read_settings(Some(&candidate_path)));
+    println!(
+        "baseline: {}, candidate: {}",
+        baseline_path.as_deref().unwrap_or("default settings"),
+        candidate_path
+    );
+    println!();
+
+    let mut num_changed = 0;
+    for (i, song) in session.songs.iter().enumerate() {
+        let messages = song.messages.iter().zip(&baseline[i]).zip(&candidate[i]);
+        for ((msg, before), after) in messages {
+            let mut names: Vec<_> = before.keys().chain(after.keys()).collect();
+            names.sort_unstable();
+            names.dedup();
+            let changes: Vec<_> = names
+                .into_iter()
+                .filter_map(|name| {
+                    let b = before.get(name).copied().unwrap_or(Mark::Hidden);
+                    let a = after.get(name).copied().unwrap_or(Mark::Hidden);
+                    (a != b).then(|| format!("{} {} -> {}", name, b, a))
+                })
+                .collect();
+            if changes.is_empty() {
+                continue;
+            }
+            num_changed += 1;
+            println!(
+                "song {} ({}) {:>6.1}s {}: {:?}",
+                i + 1,
+                song.song_name,
+                msg.time,
+                msg.player,
+                msg.text
+            );
+            for change in changes {
+                println!("    {}", change);
+            }
+        }
+    }
+    println!("{} of {} messages changed", num_changed, num_messages);
+    println!();
+
+    println!("{:<16} {:>9} {:>9}", "", "baseline", "candidate");
+    let rows = [
+        (
+            "accepted",
+            baseline_totals.accepted,
+            candidate_totals.accepted,
+        ),
+        (
+            "displayed",
+            baseline_totals.displayed,
+            candidate_totals.displayed,
+        ),
+        (
+            "songs answered",
+            baseline_totals.songs_answered,
+            candidate_totals.songs_answered,
+        ),
+    ];
+    for (name, b, a) in rows {
+        println!("{:<16} {:>9} {:>9}", name, b, a);
+    }
+}
@@ -6,7 +6,27 @@ pub enum Error {
     #[error("song number out of range ({0})")]
     SongNumberOutOfRange(i64),
     #[error("setting existing song information: {0:?}")]
-    DuplicateSongInfoReceived(crate::quiz::SongInfo),
+    DuplicateSongInfoReceived(Box<crate::quiz::SongInfo>),
+    #[error("no song information for song {0}")]
+    NoSongInfo(i64),
+    #[error("song {0} hasn't finished yet")]
+    SongNotFinished(i64),
     #[error("couldn't fetch song info: {0}")]
     FetchSongInfoError(database::Error),
+    #[error("unknown scorer `{0}`")]
+    UnknownScorer(String),
+    #[error("couldn't read rules file: {0}")]
+    ReadRulesError(std::io::Error),
+    #[error("couldn't parse rules file: {0}")]
+    ParseRulesError(serde_yaml::Error),
+    #[error("invalid rule pattern `{0}`: {1}")]
+    InvalidRule(String, regex::Error),
+    #[error("unsupported snapshot version {0}")]
+    UnsupportedSnapshotVersion(u32),
+    #[error("team `{0}` already exists")]
+    TeamExists(String),
+    #[error("team names can't be empty")]
+    EmptyTeamName,
+    #[error("no team called `{0}`")]
+    NoSuchTeam(String),
 }
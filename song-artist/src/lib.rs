@@ -2,5 +2,12 @@ mod error;
 mod quiz;
 
 pub use error::Error;
-pub use quiz::SongArtistQuiz;
-pub use quiz::{guess::GuessResult, Guess, GuessSettings};
+pub use quiz::{
+    guess::ArtistGuess, guess::GuessResult, Choice, Guess, GuessSettings, GuessTargets, Hint,
+    PlayerScore, ScorePart, ScoreRule, ScoreSettings,
+};
+pub use quiz::{DamerauLevenshtein, JaroWinkler, LengthAdaptive, Scorer, ScorerKind, TokenSet};
+pub use quiz::{Diff, Explanation, GuessExplanation};
+pub use quiz::{Rule, RuleSet, Rules};
+pub use quiz::{Season, SongType, SongTypeKind, Vintage};
+pub use quiz::{Snapshot, SongArtistQuiz, SongInfo, Teams, DEFAULT_HISTORY_LEN, SNAPSHOT_VERSION};
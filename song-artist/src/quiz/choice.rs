@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use rand::seq::SliceRandom;
+
+use crate::quiz::{Guess, SongArtistQuiz, SongInfo};
+use crate::Error;
+
+// One of the answers offered when picking the song from a list
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Choice {
+    pub song_name: String,
+    pub artist: String,
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.song_name, self.artist)
+    }
+}
+
+// The choices offered for the current song and what each player picked
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Choices {
+    pub options: Vec<Choice>,
+    // index of the right answer in the options
+    answer: usize,
+    // players can only pick once, so they can't try every option
+    picks: HashMap<String, usize>,
+}
+
+impl Choices {
+    // The answer and distractors in a random order, leaving out
+    //  any distractor that is the same as the answer or another distractor
+    pub(crate) fn new(answer: Choice, distractors: &[Choice]) -> Self {
+        let mut options = vec![answer.clone()];
+        for d in distractors {
+            if !options
+                .iter()
+                .any(|o| o.song_name.eq_ignore_ascii_case(&d.song_name))
+            {
+                options.push(d.clone());
+            }
+        }
+        options.shuffle(&mut rand::thread_rng());
+        let answer = options
+            .iter()
+            .position(|o| *o == answer)
+            .expect("answer is one of the options");
+        Choices {
+            options,
+            answer,
+            picks: HashMap::new(),
+        }
+    }
+}
+
+impl SongArtistQuiz {
+    // Sets the wrong answers to offer with a song, used if picking from choices is a target
+    pub fn set_distractors(&self, song_number: i64, choices: Vec<Choice>) -> Result<(), Error> {
+        let mut map = self.song_info.lock().expect("mutex poisoned");
+        match map.get_mut(&song_number) {
+            Some(SongInfo::Info {
+                ref mut distractors,
+                ..
+            }) => {
+                *distractors = choices;
+                Ok(())
+            }
+            _ => Err(Error::NoSongInfo(song_number)),
+        }
+    }
+
+    // Options to pick from for the current song, if picking is a target
+    pub fn choices(&self) -> Option<Vec<Choice>> {
+        let guard = self.curr_info.lock().expect("mutex poisoned");
+        let choices = guard.as_ref()?.choices.as_ref()?;
+        Some(choices.options.clone())
+    }
+
+    // Records a player picking one of the options, returning their guess
+    //  or `None` if they already picked or there is no such option
+    pub fn handle_choice(&self, player: &str, choice: usize, time: f32) -> Option<Guess> {
+        let team = self.player_team(player);
+        let mut guard = self.curr_info.lock().expect("mutex poisoned");
+        let guess_info = guard.as_mut()?;
+
+        if !guess_info.player_guesses.contains_key(player) {
+            let best = guess_info.new_best_guess();
+            guess_info.player_guesses.insert(player.to_string(), best);
+        }
+        guess_info.add_team(team.as_deref());
+
+        let choices = guess_info.choices.as_mut()?;
+        let option = choices.options.get(choice)?;
+        if choices.picks.contains_key(player) {
+            return None;
+        }
+        choices.picks.insert(player.to_string(), choice);
+
+        let text = option.to_string();
+        let guess = if choice == choices.answer {
+            Guess::Correct(text, time)
+        } else {
+            Guess::Incorrect(text, 0.0)
+        };
+        let best = guess_info
+            .player_guesses
+            .get_mut(player)
+            .expect("player guesses inserted above");
+        best.choice = Some(guess.clone());
+        // the team keeps its first correct pick
+        if let Some(best) = team.and_then(|t| guess_info.team_guesses.get_mut(&t)) {
+            if !matches!(best.choice, Some(Guess::Correct(..))) {
+                best.choice = Some(guess.clone());
+            }
+        }
+        Some(guess)
+    }
+}
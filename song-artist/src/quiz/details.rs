@@ -0,0 +1,244 @@
+// Song details that are guessed by parsing the guess rather than comparing text,
+//  which can be partially right
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SongTypeKind {
+    Opening,
+    Ending,
+    Insert,
+}
+
+// e.g. "Opening 2", "ED", "Insert Song"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SongType {
+    pub kind: SongTypeKind,
+    pub number: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Winter,
+    Spring,
+    Summer,
+    Fall,
+}
+
+// e.g. "Fall 2019", "2019"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vintage {
+    pub season: Option<Season>,
+    pub year: i32,
+}
+
+// How right a guess of a detail is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Credit {
+    Exact,
+    Partial,
+    Wrong,
+}
+
+impl SongType {
+    // Parses the type as stored ("Opening 2", "Insert Song") or typed ("op2", "ed 1", "ins")
+    pub fn parse(s: &str) -> Option<SongType> {
+        let s: String = s
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        let split = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
+        let (kind, number) = s.split_at(split);
+        let kind = match kind {
+            "op" | "opening" => SongTypeKind::Opening,
+            "ed" | "ending" => SongTypeKind::Ending,
+            "ins" | "insert" | "insertsong" => SongTypeKind::Insert,
+            _ => return None,
+        };
+        let number = if number.is_empty() {
+            None
+        } else {
+            Some(number.parse().ok()?)
+        };
+        Some(SongType { kind, number })
+    }
+
+    // Exact if the type and number match, partial if only the type does
+    pub(crate) fn credit(&self, guess: &SongType) -> Credit {
+        if self.kind != guess.kind {
+            Credit::Wrong
+        } else if self.number == guess.number {
+            Credit::Exact
+        } else {
+            Credit::Partial
+        }
+    }
+}
+
+impl Season {
+    fn parse(s: &str) -> Option<Season> {
+        match s {
+            "winter" => Some(Season::Winter),
+            "spring" => Some(Season::Spring),
+            "summer" => Some(Season::Summer),
+            "fall" | "autumn" => Some(Season::Fall),
+            _ => None,
+        }
+    }
+}
+
+impl Vintage {
+    // Parses a year with an optional season in either order, e.g. "Fall 2019", "2019 fall", "fall 19"
+    pub fn parse(s: &str) -> Option<Vintage> {
+        let mut season = None;
+        let mut year = None;
+        for word in s
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            let word = word.to_lowercase();
+            if let Some(s) = Season::parse(&word) {
+                if season.replace(s).is_some() {
+                    return None;
+                }
+            } else if word.chars().all(|c| c.is_ascii_digit()) {
+                let y: i32 = match word.len() {
+                    4 => word.parse().ok()?,
+                    // two digit years
+                    2 => {
+                        let y: i32 = word.parse().ok()?;
+                        if y >= 60 {
+                            1900 + y
+                        } else {
+                            2000 + y
+                        }
+                    }
+                    _ => return None,
+                };
+                if year.replace(y).is_some() {
+                    return None;
+                }
+            } else {
+                return None;
+            }
+        }
+        Some(Vintage {
+            season,
+            year: year?,
+        })
+    }
+
+    // Exact if the year and season match, partial if the year is off by one
+    //  or the season is wrong or missing
+    pub(crate) fn credit(&self, guess: &Vintage) -> Credit {
+        match (self.year - guess.year).abs() {
+            0 if self.season.is_none() || self.season == guess.season => Credit::Exact,
+            0 | 1 => Credit::Partial,
+            _ => Credit::Wrong,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song_type(kind: SongTypeKind, number: Option<u32>) -> Option<SongType> {
+        Some(SongType { kind, number })
+    }
+
+    fn vintage(season: Option<Season>, year: i32) -> Option<Vintage> {
+        Some(Vintage { season, year })
+    }
+
+    #[test]
+    fn parse_song_type() {
+        use SongTypeKind::*;
+        let cases = [
+            ("Opening 2", song_type(Opening, Some(2))),
+            ("Ending 1", song_type(Ending, Some(1))),
+            ("Insert Song", song_type(Insert, None)),
+            ("op", song_type(Opening, None)),
+            ("ed10", song_type(Ending, Some(10))),
+            ("ins", song_type(Insert, None)),
+            ("insert", song_type(Insert, None)),
+            ("op 02", song_type(Opening, Some(2))),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(SongType::parse(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_song_type_case_and_spacing() {
+        let expected = song_type(SongTypeKind::Opening, Some(2));
+        for text in ["OP2", "op 2", "  Op  2 ", "op-2", "OPENING 2", "o p 2"] {
+            assert_eq!(SongType::parse(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_song_type_rejects() {
+        for text in [
+            "",
+            "2",
+            "opening song",
+            "op2a",
+            "op 2 3x",
+            "oped",
+            "song",
+            "op 99999999999",
+        ] {
+            assert_eq!(SongType::parse(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_vintage() {
+        use Season::*;
+        let cases = [
+            ("Fall 2019", vintage(Some(Fall), 2019)),
+            ("2019 fall", vintage(Some(Fall), 2019)),
+            ("autumn 2019", vintage(Some(Fall), 2019)),
+            ("Winter 2006", vintage(Some(Winter), 2006)),
+            ("2006", vintage(None, 2006)),
+            ("spring 19", vintage(Some(Spring), 2019)),
+            ("summer 99", vintage(Some(Summer), 1999)),
+            ("60", vintage(None, 1960)),
+            ("59", vintage(None, 2059)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Vintage::parse(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_vintage_case_and_spacing() {
+        let expected = vintage(Some(Season::Fall), 2019);
+        for text in [
+            "FALL 2019",
+            "fall  2019",
+            " Fall 2019 ",
+            "fall-2019",
+            "2019/Fall",
+        ] {
+            assert_eq!(Vintage::parse(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parse_vintage_rejects() {
+        for text in [
+            "",
+            "fall",
+            "fall 2019 spring",
+            "2019 2020",
+            "201",
+            "20190",
+            "fall 2019 anime",
+            "19th",
+            "fall2019",
+        ] {
+            assert_eq!(Vintage::parse(text), None, "{:?}", text);
+        }
+    }
+}
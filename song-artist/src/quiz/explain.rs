@@ -0,0 +1,336 @@
+use std::fmt;
+
+use crate::quiz::settings::is_folded;
+use crate::quiz::{GuessSettings, Name, SongArtistQuiz, SongInfo};
+use crate::Error;
+
+// Why a guess got the score it did against an answer
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub score: f32,
+    pub accepted: bool,
+    // the guess and the form of the answer it was closest to, as they were compared
+    pub guess: String,
+    pub answer: String,
+    // how the guess lines up with the answer, character by character
+    pub diff: Vec<Diff>,
+    // normalisation that changed the guess or the answer before comparing
+    pub steps: Vec<String>,
+}
+
+// A run of characters in the alignment of a guess with an answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diff {
+    Same(String),
+    // in the answer but not the guess
+    Missing(String),
+    // in the guess but not the answer
+    Extra(String),
+    // guess, answer
+    Wrong(String, String),
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diff::Same(s) => f.write_str(s),
+            Diff::Missing(s) => write!(f, "[+{}]", s),
+            Diff::Extra(s) => write!(f, "[-{}]", s),
+            Diff::Wrong(g, a) => write!(f, "[{}→{}]", g, a),
+        }
+    }
+}
+
+// Explanations for each answer being guessed in a song
+#[derive(Debug, Clone, Default)]
+pub struct GuessExplanation {
+    pub song_name: Option<Explanation>,
+    // the artist closest to the guess
+    pub artist: Option<Explanation>,
+    pub anime: Option<Explanation>,
+}
+
+// A form of an answer that guesses are compared to
+struct Form {
+    name: Name,
+    // the text it was normalised from
+    source: String,
+    // anything else worth mentioning about where it came from
+    note: Option<String>,
+}
+
+impl GuessSettings {
+    // Explains the score of a song name guess, as it would be scored in a quiz
+    pub fn explain_song_name(&self, guess: &str, answer: &str) -> Explanation {
+        self.explain_names(guess, &[answer])
+    }
+
+    // Explains the score of an artist guess against a single artist
+    pub fn explain_artist(&self, guess: &str, artist: &str) -> Explanation {
+        let mut forms = self.artist_forms(artist, None);
+        for alias in self.aliases(artist) {
+            forms.extend(self.artist_forms(alias, Some(format!("alias `{}`", alias))));
+        }
+        self.explain(guess, forms)
+    }
+
+    // Compared against every form of each of the names
+    fn explain_names<S: AsRef<str>>(&self, guess: &str, names: &[S]) -> Explanation {
+        let forms = names
+            .iter()
+            .flat_map(|n| {
+                self.rules.variants(n.as_ref()).into_iter().map(|v| Form {
+                    name: self.name(self.normalise(v.chars())),
+                    source: n.as_ref().to_string(),
+                    note: None,
+                })
+            })
+            .collect();
+        self.explain(guess, forms)
+    }
+
+    fn artist_forms(&self, artist: &str, note: Option<String>) -> Vec<Form> {
+        let mut names = Vec::new();
+        self.push_artist_names(&mut names, artist);
+        names
+            .into_iter()
+            .map(|name| Form {
+                name,
+                source: artist.to_string(),
+                note: note.clone(),
+            })
+            .collect()
+    }
+
+    // Finds the closest pair of guess and answer forms, the same way as scoring does
+    fn explain(&self, guess: &str, forms: Vec<Form>) -> Explanation {
+        let guess_names = self.normalise_sn(guess);
+        let mut best: Option<(f32, &str, &str, bool, &Form)> = None;
+        for (g, form) in itertools::iproduct!(&guess_names, &forms) {
+            let t = &form.name;
+            let mut pairs = vec![(g.normalised.as_str(), t.normalised.as_str(), false)];
+            if g.romanised.is_some() || t.romanised.is_some() {
+                let g = g.romanised.as_ref().unwrap_or(&g.normalised);
+                let t = t.romanised.as_ref().unwrap_or(&t.normalised);
+                pairs.push((g, t, true));
+            }
+            for (g, t, romanised) in pairs {
+                let score = self.compare(g, t);
+                if best.is_none_or(|b| score > b.0) {
+                    best = Some((score, g, t, romanised, form));
+                }
+            }
+        }
+
+        let Some((score, g, t, romanised, form)) = best else {
+            return Explanation {
+                score: 0.0,
+                accepted: false,
+                guess: String::new(),
+                answer: String::new(),
+                diff: Vec::new(),
+                steps: Vec::new(),
+            };
+        };
+        let mut steps = self.steps(guess);
+        steps.extend(self.steps(&form.source));
+        steps.extend(form.note.clone());
+        if romanised {
+            steps.push("compared in romaji".to_string());
+        }
+        let mut unique = Vec::new();
+        for step in steps {
+            if !unique.contains(&step) {
+                unique.push(step);
+            }
+        }
+
+        let (g, t) = (g.trim(), t.trim());
+        Explanation {
+            score,
+            accepted: score > self.answer_threshold,
+            guess: g.to_string(),
+            answer: t.to_string(),
+            diff: diff(g, t),
+            steps: unique,
+        }
+    }
+
+    // What normalising does to the text
+    fn steps(&self, text: &str) -> Vec<String> {
+        let text = text.trim();
+        let mut steps = self.rules.applied(text);
+        if !self.case_sensitive && text.chars().any(char::is_uppercase) {
+            steps.push("case ignored".to_string());
+        }
+        if !self.space_sensitive && text.chars().any(char::is_whitespace) {
+            steps.push("spaces ignored".to_string());
+        }
+        if !self.punc_sensitive
+            && text
+                .chars()
+                .any(|c| !c.is_alphanumeric() && !c.is_whitespace())
+        {
+            steps.push("punctuation ignored".to_string());
+        }
+        if text.chars().any(is_folded) {
+            steps.push("accents, widths and kana folded".to_string());
+        }
+        steps
+    }
+}
+
+// Aligns the guess with the answer using the fewest insertions,
+//  deletions and substitutions, grouping runs of the same kind
+fn diff(guess: &str, answer: &str) -> Vec<Diff> {
+    let g: Vec<char> = guess.chars().collect();
+    let a: Vec<char> = answer.chars().collect();
+    let (n, m) = (g.len(), a.len());
+
+    // edit distance between each pair of prefixes
+    let mut d = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let sub = d[i - 1][j - 1] + usize::from(g[i - 1] != a[j - 1]);
+            d[i][j] = sub.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+        }
+    }
+
+    // walk back from the end to find the edits
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && d[i][j] == d[i - 1][j - 1] + usize::from(g[i - 1] != a[j - 1]) {
+            ops.push((Some(g[i - 1]), Some(a[j - 1])));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && d[i][j] == d[i - 1][j] + 1 {
+            ops.push((Some(g[i - 1]), None));
+            i -= 1;
+        } else {
+            ops.push((None, Some(a[j - 1])));
+            j -= 1;
+        }
+    }
+
+    let mut diff: Vec<Diff> = Vec::new();
+    for op in ops.into_iter().rev() {
+        match (op, diff.last_mut()) {
+            ((Some(x), Some(y)), Some(Diff::Same(s))) if x == y => s.push(x),
+            ((Some(x), Some(y)), _) if x == y => diff.push(Diff::Same(x.to_string())),
+            ((Some(x), Some(y)), Some(Diff::Wrong(gs, ys))) => {
+                gs.push(x);
+                ys.push(y);
+            }
+            ((Some(x), Some(y)), _) => diff.push(Diff::Wrong(x.to_string(), y.to_string())),
+            ((Some(x), None), Some(Diff::Extra(s))) => s.push(x),
+            ((Some(x), None), _) => diff.push(Diff::Extra(x.to_string())),
+            ((None, Some(y)), Some(Diff::Missing(s))) => s.push(y),
+            ((None, Some(y)), _) => diff.push(Diff::Missing(y.to_string())),
+            ((None, None), _) => {}
+        }
+    }
+    diff
+}
+
+impl SongArtistQuiz {
+    // Explains how a guess would score against each answer for a finished song that is
+    //  still kept, using the current settings.
+    // Songs that haven't finished aren't explained, as that would give away the answer
+    pub fn explain(&self, song_number: i64, guess: &str) -> Result<GuessExplanation, Error> {
+        if song_number > self.last_finished_song() {
+            return Err(Error::SongNotFinished(song_number));
+        }
+        let settings = self.guess_settings.lock().expect("mutex poisoned").clone();
+        let map = self.song_info.lock().expect("mutex poisoned");
+        let Some(SongInfo::Info {
+            song_name,
+            artist,
+            anime_names,
+            artist_aliases,
+            ..
+        }) = map.get(&song_number)
+        else {
+            return Err(Error::NoSongInfo(song_number));
+        };
+        let settings = settings.with_song_aliases(artist_aliases);
+
+        let targets = settings.targets;
+        let artist = if targets.artist {
+            settings
+                .split_re
+                .split(artist)
+                .map(|a| settings.explain_artist(guess, a))
+                .max_by(|a, b| a.score.total_cmp(&b.score))
+        } else {
+            None
+        };
+        Ok(GuessExplanation {
+            song_name: targets
+                .song_name
+                .then(|| settings.explain_song_name(guess, song_name)),
+            artist,
+            anime: (targets.anime && !anime_names.is_empty())
+                .then(|| settings.explain_names(guess, anime_names)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song() -> SongInfo {
+        SongInfo::Info {
+            song_name: "Renai Circulation".to_string(),
+            artist: "Kana Hanazawa".to_string(),
+            anime_names: vec!["Bakemonogatari".to_string()],
+            song_type: None,
+            vintage: None,
+            distractors: Vec::new(),
+            artist_aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn explain_finished_songs_only() {
+        let quiz = SongArtistQuiz::new();
+        quiz.set_info(1, song()).unwrap();
+        quiz.set_info(2, song()).unwrap();
+        quiz.set_song_number(1);
+        assert_eq!(quiz.last_finished_song(), 0);
+        let current = quiz.explain(1, "renai circulation");
+        assert!(matches!(current, Err(Error::SongNotFinished(1))));
+        let next = quiz.explain(2, "renai circulation");
+        assert!(matches!(next, Err(Error::SongNotFinished(2))));
+
+        quiz.finish_song();
+        assert_eq!(quiz.last_finished_song(), 1);
+        let explanation = quiz.explain(1, "renai circulation").unwrap();
+        assert!(explanation.song_name.unwrap().accepted);
+
+        quiz.set_song_number(2);
+        assert_eq!(quiz.last_finished_song(), 1);
+        assert!(quiz.explain(1, "renai circulation").is_ok());
+        assert!(matches!(
+            quiz.explain(2, "renai"),
+            Err(Error::SongNotFinished(2))
+        ));
+    }
+
+    #[test]
+    fn explain_missing_song() {
+        let quiz = SongArtistQuiz::new();
+        quiz.set_song_number(3);
+        assert!(matches!(
+            quiz.explain(2, "renai"),
+            Err(Error::NoSongInfo(2))
+        ));
+    }
+}
@@ -1,23 +1,102 @@
 use crate::quiz;
-use crate::quiz::GuessInfo;
+use crate::quiz::details::{Credit, SongType, Vintage};
+use crate::quiz::{BestGuess, GuessInfo, GuessSettings, Name};
 
+mod assign;
 mod edit;
 use edit::EditCommand;
 
-#[derive(Debug, Clone)]
+// One of the answers guessed by comparing text, which edits can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum Field {
+    SongName,
+    // index of the artist in the credited artists
+    Artist(usize),
+    Anime,
+}
+
+// A field changed by an edit, with the best guesses it replaced so that it can be undone
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct FieldEdit {
+    field: Field,
+    text: String,
+    // (before, after) for the combined, the player's and their team's best guess,
+    //  if the edit replaced it
+    global: Option<(Guess, Guess)>,
+    player: Option<(Guess, Guess)>,
+    team: Option<(Guess, Guess)>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Guess {
     // guess, %
     Incorrect(String, f32),
     // TODO: make time optional
     // guess, time in seconds
     Correct(String, f32),
+    // guess, fraction of the points, time in seconds
+    Partial(String, f32, f32),
 }
 
 impl Guess {
-    fn correct(&self) -> bool {
+    pub(crate) fn correct(&self) -> bool {
         match self {
             Guess::Correct(..) => true,
-            Guess::Incorrect(..) => false,
+            Guess::Incorrect(..) | Guess::Partial(..) => false,
+        }
+    }
+
+    // Scores at or below this can't replace the guess
+    fn floor(&self, threshold: f32) -> f32 {
+        match self {
+            Guess::Correct(..) => f32::INFINITY,
+            Guess::Partial(..) => threshold,
+            Guess::Incorrect(_, percent) => percent.min(threshold),
+        }
+    }
+
+    // Replaces this with the new guess if it is correct or closer than before,
+    //  returning whether it was replaced
+    fn update(&mut self, guess: &str, score: f32, time: f32, threshold: f32) -> bool {
+        match self {
+            Guess::Correct(..) => false,
+            Guess::Partial(..) => {
+                if score > threshold {
+                    *self = Guess::Correct(guess.to_string(), time);
+                    true
+                } else {
+                    false
+                }
+            }
+            Guess::Incorrect(_, percent) => {
+                if score > threshold {
+                    *self = Guess::Correct(guess.to_string(), time);
+                    true
+                } else if score > *percent {
+                    *self = Guess::Incorrect(guess.to_string(), score);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    // Replaces this with the new guess if it gets more credit than before,
+    //  returning whether it was replaced
+    fn update_credit(&mut self, guess: &str, credit: Credit, partial: f32, time: f32) -> bool {
+        match (credit, &*self) {
+            (_, Guess::Correct(..))
+            | (Credit::Wrong, _)
+            | (Credit::Partial, Guess::Partial(..)) => false,
+            (Credit::Exact, _) => {
+                *self = Guess::Correct(guess.to_string(), time);
+                true
+            }
+            (Credit::Partial, Guess::Incorrect(..)) => {
+                *self = Guess::Partial(guess.to_string(), partial, time);
+                true
+            }
         }
     }
 }
@@ -30,130 +109,489 @@ impl Default for Guess {
 
 pub struct GuessResult {
     pub song_guess: Option<Guess>,
-    pub artist_guesses: Vec<Guess>,
+    pub anime_guess: Option<Guess>,
+    pub song_type_guess: Option<Guess>,
+    pub vintage_guess: Option<Guess>,
+    pub artist_guesses: Vec<ArtistGuess>,
     pub num_correct_artists: usize,
     pub total_artists: usize,
+    // the guess undid the player's last edit
+    pub undone: bool,
+}
+
+pub struct ArtistGuess {
+    // index of the artist in the credited artists
+    pub index: usize,
+    // part of the message that was matched to the artist, or the edited guess
+    pub fragment: String,
+    pub guess: Guess,
 }
 
 impl quiz::SongArtistQuiz {
-    pub fn handle_guess(&self, guess: &str, time: f32) -> Option<GuessResult> {
+    // Checks a guess from a player, updating their own best guesses, their team's
+    //  and the combined best guess from everyone.
+    // Returns the combined best guesses that should be displayed, if any changed
+    pub fn handle_guess(&self, player: &str, guess: &str, time: f32) -> Option<GuessResult> {
+        let team = self.player_team(player);
         let mut guard = self.curr_info.lock().expect("mutex poisoned");
         let guess_info = guard.as_mut()?;
 
-        // handle edits
-        let edit = EditCommand::new(guess);
-
-        // TODO: move this when handling artists differently
-        let guess_norm = guess_info.settings.normalise_sn(guess);
-
-        // check song name
-        let song_guess = check_song_name(&guess_norm, &guess, guess_info, edit, time);
+        if !guess_info.player_guesses.contains_key(player) {
+            let best = guess_info.new_best_guess();
+            guess_info.player_guesses.insert(player.to_string(), best);
+        }
+        guess_info.add_team(team.as_deref());
 
-        let artists = &guess_info.artists;
+        let GuessInfo {
+            ref song_name,
+            ref artists,
+            ref anime,
+            ref song_type,
+            ref vintage,
+            ref mut global_best_guess,
+            ref mut player_guesses,
+            ref mut team_guesses,
+            ref mut normalised,
+            ref settings,
+            ..
+        } = *guess_info;
 
-        // check artists
-        let mut display_artists = vec![false; artists.len()];
-        let mut num_correct_artists = 0;
+        let player_best = player_guesses
+            .get_mut(player)
+            .expect("player guesses inserted above");
+        let mut team_best = team.and_then(|t| team_guesses.get_mut(&t));
 
-        // TODO: check each artist in split
-        // let artists: Vec<_> = settings.split_re
-        //     .split(guess)
-        //     .map(|a| settings.normalise_artist(a))
+        let mut song_guess = None;
+        let mut anime_guess = None;
+        let mut song_type_guess = None;
+        let mut vintage_guess = None;
+        let mut artist_guesses = Vec::new();
+        let mut undone = false;
 
-        let settings = &guess_info.settings;
-        let best_artists = &mut guess_info.global_best_guess.artists;
-        for (best_artist_guess, artist, display) in
-            itertools::izip!(best_artists, artists, &mut display_artists)
-        {
-            if let Guess::Incorrect(ref old, percent) = best_artist_guess {
-                let mut guess_artist = settings.check_artist(&guess_norm, artist);
-                let mut edited = None;
-                if let Some(s) = edit.apply(old) {
-                    let edit_guess = settings.check_artist(&settings.normalise_sn(&s), artist);
-                    if edit_guess > guess_artist {
-                        guess_artist = edit_guess;
-                        edited = Some(s);
+        match EditCommand::new(guess) {
+            EditCommand::Undo => {
+                if let Some(edited) = player_best.edits.pop() {
+                    for edit in edited.iter().rev() {
+                        let team = team_best
+                            .as_deref_mut()
+                            .and_then(|t| t.field_mut(edit.field));
+                        restore(global_best_guess.field_mut(edit.field), &edit.global);
+                        restore(player_best.field_mut(edit.field), &edit.player);
+                        restore(team, &edit.team);
                     }
+                    undone = true;
+                }
+            }
+            EditCommand::Edit(target, edit) => {
+                let fields = song_name
+                    .iter()
+                    .map(|_| Field::SongName)
+                    .chain((0..artists.len()).map(Field::Artist))
+                    .chain(anime.iter().map(|_| Field::Anime))
+                    .filter(|&f| target.is_none_or(|t| t == f));
+                let mut edited = Vec::new();
+                for field in fields {
+                    let Some(base) = player_best.edit_base(field) else {
+                        continue;
+                    };
+                    let text = edit.apply(&base);
+                    let text_norm = normalised.get(settings, &text);
+                    let min = floor(
+                        settings,
+                        global_best_guess.field(field),
+                        player_best.field(field),
+                        team_best.as_deref().and_then(|t| t.field(field)),
+                    );
+                    let score = match field {
+                        Field::SongName => song_name
+                            .as_ref()
+                            .map(|t| settings.check_sn(&text_norm, t, min)),
+                        Field::Artist(i) => artists
+                            .get(i)
+                            .map(|a| settings.check_artist(&text_norm, a, min)),
+                        Field::Anime => anime
+                            .as_ref()
+                            .map(|t| settings.check_sn(&text_norm, t, min)),
+                    };
+                    let before = (
+                        global_best_guess.field(field).cloned(),
+                        player_best.field(field).cloned(),
+                        team_best.as_deref().and_then(|t| t.field(field)).cloned(),
+                    );
+                    let team = team_best.as_deref_mut().and_then(|t| t.field_mut(field));
+                    let result = score
+                        .zip(global_best_guess.field_mut(field))
+                        .zip(player_best.field_mut(field))
+                        .and_then(|((score, global), player)| {
+                            update_best(settings, global, player, team, &text, score, time)
+                        });
+                    if let Some(g) = result {
+                        match field {
+                            Field::SongName => song_guess = Some(g),
+                            Field::Anime => anime_guess = Some(g),
+                            Field::Artist(index) => artist_guesses.push(ArtistGuess {
+                                index,
+                                fragment: text.clone(),
+                                guess: g,
+                            }),
+                        }
+                    }
+                    edited.push(FieldEdit {
+                        field,
+                        global: replaced(before.0, global_best_guess.field(field)),
+                        player: replaced(before.1, player_best.field(field)),
+                        team: replaced(before.2, team_best.as_deref().and_then(|t| t.field(field))),
+                        text,
+                    });
+                }
+                if !edited.is_empty() {
+                    player_best.edits.push(edited);
                 }
+            }
+            EditCommand::None => {
+                // a new guess starts editing from the best guesses again
+                player_best.edits.clear();
+
+                let guess_norm = normalised.get(settings, guess);
+
+                // check song name and anime against the whole guess
+                let check_whole = |target: &Option<Vec<Name>>,
+                                   global: &mut Option<Guess>,
+                                   player: &mut Option<Guess>,
+                                   team: Option<&mut Guess>| {
+                    let (target, global, player) =
+                        (target.as_ref()?, global.as_mut()?, player.as_mut()?);
+                    let min = floor(settings, Some(&*global), Some(&*player), team.as_deref());
+                    let score = settings.check_sn(&guess_norm, target, min);
+                    update_best(settings, global, player, team, guess, score, time)
+                };
+                song_guess = check_whole(
+                    song_name,
+                    &mut global_best_guess.song_name,
+                    &mut player_best.song_name,
+                    team_best.as_deref_mut().and_then(|t| t.song_name.as_mut()),
+                );
+                anime_guess = check_whole(
+                    anime,
+                    &mut global_best_guess.anime,
+                    &mut player_best.anime,
+                    team_best.as_deref_mut().and_then(|t| t.anime.as_mut()),
+                );
+
+                // check song type and vintage if the guess can be read as one
+                let partial = settings.scoring.partial_credit;
+                song_type_guess = song_type.zip(SongType::parse(guess)).and_then(|(t, g)| {
+                    update_best_credit(
+                        global_best_guess.song_type.as_mut()?,
+                        player_best.song_type.as_mut()?,
+                        team_best.as_deref_mut().and_then(|t| t.song_type.as_mut()),
+                        guess,
+                        t.credit(&g),
+                        partial,
+                        time,
+                    )
+                });
+                vintage_guess = vintage.zip(Vintage::parse(guess)).and_then(|(v, g)| {
+                    update_best_credit(
+                        global_best_guess.vintage.as_mut()?,
+                        player_best.vintage.as_mut()?,
+                        team_best.as_deref_mut().and_then(|t| t.vintage.as_mut()),
+                        guess,
+                        v.credit(&g),
+                        partial,
+                        time,
+                    )
+                });
+
+                // check artists
+                //  each part of the guess can only match one artist, so a guess of
+                //  "LiSA, Aimer" can get both but "LiSA" alone can't get two
+                let fragments: Vec<&str> = settings
+                    .split_re
+                    .split(guess)
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .collect();
+                // score of each fragment against each artist,
+                //  leaving out scores that wouldn't change any of the best guesses
+                let mins: Vec<f32> = (0..artists.len())
+                    .map(|i| {
+                        floor(
+                            settings,
+                            global_best_guess.artists.get(i),
+                            player_best.artists.get(i),
+                            team_best.as_deref().and_then(|t| t.artists.get(i)),
+                        )
+                    })
+                    .collect();
+                let scores: Vec<Vec<f32>> = fragments
+                    .iter()
+                    .map(|&fragment| {
+                        let fragment_norm = normalised.get(settings, fragment);
+                        artists
+                            .iter()
+                            .zip(&mins)
+                            .map(|(artist, &min)| {
+                                settings.check_artist(&fragment_norm, artist, min)
+                            })
+                            .collect()
+                    })
+                    .collect();
+                // artists the player already has don't need matching again
+                let weights: Vec<Vec<f32>> = scores
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .zip(&player_best.artists)
+                            .map(|(score, g)| if g.correct() { 0.0 } else { *score })
+                            .collect()
+                    })
+                    .collect();
 
-                if guess_artist > settings.answer_threshold {
-                    *best_artist_guess =
-                        Guess::Correct(edited.unwrap_or_else(|| guess.to_string()), time);
-                    *display = true;
-                    num_correct_artists += 1;
-                } else if guess_artist > *percent {
-                    *best_artist_guess =
-                        Guess::Incorrect(edited.unwrap_or_else(|| guess.to_string()), guess_artist);
-                    *display = guess_artist > settings.display_threshold;
+                let mut assignment = assign::assign(&weights, artists.len());
+                assignment.sort_unstable_by_key(|&(_, index)| index);
+                for (fragment, index) in assignment {
+                    if let Some(g) = update_best(
+                        settings,
+                        &mut global_best_guess.artists[index],
+                        &mut player_best.artists[index],
+                        team_best.as_deref_mut().map(|t| &mut t.artists[index]),
+                        fragments[fragment],
+                        scores[fragment][index],
+                        time,
+                    ) {
+                        artist_guesses.push(ArtistGuess {
+                            index,
+                            fragment: fragments[fragment].to_string(),
+                            guess: g,
+                        });
+                    }
                 }
-            } else {
-                num_correct_artists += 1;
             }
         }
 
-        let artist_guesses: Vec<Guess> = display_artists
-            .into_iter()
-            .zip(guess_info.global_best_guess.artists.iter())
-            .filter_map(|(display, guess)| if display { Some(guess.clone()) } else { None })
-            .collect();
+        let num_correct_artists = global_best_guess
+            .artists
+            .iter()
+            .filter(|g| g.correct())
+            .count();
 
-        if song_guess.is_none() && artist_guesses.is_empty() {
+        if song_guess.is_none()
+            && anime_guess.is_none()
+            && song_type_guess.is_none()
+            && vintage_guess.is_none()
+            && artist_guesses.is_empty()
+            && !undone
+        {
             return None;
         }
 
         Some(GuessResult {
             song_guess,
+            anime_guess,
+            song_type_guess,
+            vintage_guess,
             artist_guesses,
             num_correct_artists,
             total_artists: artists.len(),
+            undone,
         })
     }
 
     pub fn correct(&self) -> bool {
         let guard = self.curr_info.lock().expect("mutex poisoned");
         if let Some(info) = guard.as_ref() {
-            let best = &info.global_best_guess;
-            best.song_name.correct() && best.artists.iter().all(Guess::correct)
+            info.global_best_guess.correct()
         } else {
             false
         }
     }
 }
 
-// returns song name guess if it should be displayed
-fn check_song_name(
-    guess_norm: &str,
-    guess_raw: &str,
-    guess_info: &mut GuessInfo,
-    edit: EditCommand,
+// Scores at or below this wouldn't change the player's, their team's or the combined
+//  best guess, so the guess doesn't need scoring exactly
+fn floor(
+    settings: &GuessSettings,
+    global: Option<&Guess>,
+    player: Option<&Guess>,
+    team: Option<&Guess>,
+) -> f32 {
+    let threshold = settings.answer_threshold;
+    let floor = |g: Option<&Guess>| g.map_or(f32::INFINITY, |g| g.floor(threshold));
+    floor(global).min(floor(player)).min(floor(team))
+}
+
+// The guess from before an edit and the one it was replaced with, if it was
+fn replaced(before: Option<Guess>, after: Option<&Guess>) -> Option<(Guess, Guess)> {
+    let (before, after) = (before?, after?);
+    (before != *after).then(|| (before, after.clone()))
+}
+
+// Puts back the guess an edit replaced, unless another guess has replaced it since
+fn restore(guess: Option<&mut Guess>, replaced: &Option<(Guess, Guess)>) {
+    if let (Some(guess), Some((before, after))) = (guess, replaced) {
+        if guess == after {
+            *guess = before.clone();
+        }
+    }
+}
+
+// Updates the player's, their team's and the combined best guess,
+//  returning the combined best guess if it should be displayed
+fn update_best(
+    settings: &GuessSettings,
+    global: &mut Guess,
+    player: &mut Guess,
+    team: Option<&mut Guess>,
+    guess: &str,
+    score: f32,
     time: f32,
 ) -> Option<Guess> {
-    let song_name = &guess_info.song_name;
-    let best_sn = &mut guess_info.global_best_guess.song_name;
-    let settings = &guess_info.settings;
-
-    let mut display_song_name = false;
-    if let Guess::Incorrect(ref old, percent) = best_sn {
-        let mut guess_sn = settings.check_sn(guess_norm, song_name);
-        let mut edited = None;
-        if let Some(s) = edit.apply(old) {
-            let edit_guess = settings.check_sn(&settings.normalise_sn(&s), song_name);
-            if edit_guess > guess_sn {
-                guess_sn = edit_guess;
-                edited = Some(s);
-            }
-        }
-        if guess_sn > settings.answer_threshold {
-            *best_sn = Guess::Correct(edited.unwrap_or_else(|| guess_raw.to_string()), time);
-            display_song_name = true;
-        } else if guess_sn > *percent {
-            *best_sn = Guess::Incorrect(edited.unwrap_or_else(|| guess_raw.to_string()), guess_sn);
-            display_song_name = guess_sn > settings.display_threshold;
-        }
+    player.update(guess, score, time, settings.answer_threshold);
+    if let Some(team) = team {
+        team.update(guess, score, time, settings.answer_threshold);
+    }
+    if global.update(guess, score, time, settings.answer_threshold)
+        && (global.correct() || score > settings.display_threshold)
+    {
+        Some(global.clone())
+    } else {
+        None
+    }
+}
+
+// Updates the player's, their team's and the combined best guess for a detail,
+//  returning the combined best guess if it got more credit
+fn update_best_credit(
+    global: &mut Guess,
+    player: &mut Guess,
+    team: Option<&mut Guess>,
+    guess: &str,
+    credit: Credit,
+    partial: f32,
+    time: f32,
+) -> Option<Guess> {
+    player.update_credit(guess, credit, partial, time);
+    if let Some(team) = team {
+        team.update_credit(guess, credit, partial, time);
     }
-    if display_song_name {
-        Some(best_sn.clone())
+    if global.update_credit(guess, credit, partial, time) {
+        Some(global.clone())
     } else {
         None
     }
 }
+
+impl BestGuess {
+    fn field(&self, field: Field) -> Option<&Guess> {
+        match field {
+            Field::SongName => self.song_name.as_ref(),
+            Field::Artist(index) => self.artists.get(index),
+            Field::Anime => self.anime.as_ref(),
+        }
+    }
+
+    fn field_mut(&mut self, field: Field) -> Option<&mut Guess> {
+        match field {
+            Field::SongName => self.song_name.as_mut(),
+            Field::Artist(index) => self.artists.get_mut(index),
+            Field::Anime => self.anime.as_mut(),
+        }
+    }
+
+    // Text the next edit of the field applies to: the last edit of it since
+    //  the last new guess, or else the best guess so far if there is one
+    fn edit_base(&self, field: Field) -> Option<String> {
+        let best = match self.field(field)? {
+            Guess::Correct(..) => return None,
+            Guess::Incorrect(text, _) | Guess::Partial(text, ..) => text,
+        };
+        let edited = self
+            .edits
+            .iter()
+            .rev()
+            .flatten()
+            .find(|e| e.field == field)
+            .map(|e| &e.text);
+        edited
+            .or(Some(best))
+            .filter(|text| !text.is_empty())
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{SongArtistQuiz, SongInfo};
+
+    fn quiz() -> SongArtistQuiz {
+        let quiz = SongArtistQuiz::new();
+        let info = SongInfo::Info {
+            song_name: "Kimi no Shiranai Monogatari".to_string(),
+            artist: "supercell".to_string(),
+            anime_names: vec!["Bakemonogatari".to_string()],
+            song_type: None,
+            vintage: None,
+            distractors: Vec::new(),
+            artist_aliases: Vec::new(),
+        };
+        quiz.set_info(1, info).unwrap();
+        quiz.set_song_number(1);
+        quiz
+    }
+
+    // the player's and the combined best song name guess
+    fn song_names(quiz: &SongArtistQuiz, player: &str) -> (Guess, Guess) {
+        let guard = quiz.curr_info.lock().unwrap();
+        let info = guard.as_ref().unwrap();
+        let player = info.player_guesses[player].song_name.clone().unwrap();
+        (player, info.global_best_guess.song_name.clone().unwrap())
+    }
+
+    #[test]
+    fn undo_edit_then_guess_again() {
+        let quiz = quiz();
+        quiz.handle_guess("a", "kimi no shiranai", 1.0);
+        let (before, _) = song_names(&quiz, "a");
+        assert!(!before.correct());
+
+        let result = quiz.handle_guess("a", ".an: monogatari", 2.0).unwrap();
+        assert!(result.song_guess.unwrap().correct());
+        assert!(song_names(&quiz, "a").0.correct());
+
+        let result = quiz.handle_guess("a", ".u", 3.0).unwrap();
+        assert!(result.undone);
+        assert_eq!(song_names(&quiz, "a"), (before.clone(), before));
+        assert!(quiz.handle_guess("a", ".u", 3.5).is_none());
+
+        let result = quiz
+            .handle_guess("a", "kimi no shiranai monogatari", 4.0)
+            .unwrap();
+        assert_eq!(
+            result.song_guess,
+            Some(Guess::Correct(
+                "kimi no shiranai monogatari".to_string(),
+                4.0
+            ))
+        );
+    }
+
+    #[test]
+    fn undo_keeps_later_guesses() {
+        let quiz = quiz();
+        quiz.handle_guess("a", "kimi no", 1.0);
+        let (before, _) = song_names(&quiz, "a");
+        quiz.handle_guess("a", ".an: shiranai", 2.0);
+        let (edited, _) = song_names(&quiz, "a");
+        assert!(matches!(edited, Guess::Incorrect(ref text, _) if text == "kimi no shiranai"));
+
+        // someone else gets closer after the edit
+        quiz.handle_guess("b", "kimi no shiranai mono", 3.0)
+            .unwrap();
+        let (later, _) = song_names(&quiz, "b");
+
+        assert!(quiz.handle_guess("a", ".u", 4.0).unwrap().undone);
+        assert_eq!(song_names(&quiz, "a"), (before, later));
+    }
+}
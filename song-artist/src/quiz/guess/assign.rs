@@ -0,0 +1,83 @@
+// Assignment of the fragments of a guess to artists,
+//  so that each fragment is matched to at most one artist and vice versa
+
+// Returns (row, column) pairs maximising the total weight,
+//  with every row of the smaller side assigned to a different column
+pub(crate) fn assign(weights: &[Vec<f32>], num_cols: usize) -> Vec<(usize, usize)> {
+    let num_rows = weights.len();
+    if num_rows == 0 || num_cols == 0 {
+        return Vec::new();
+    }
+    if num_rows <= num_cols {
+        hungarian(num_rows, num_cols, |r, c| weights[r][c])
+    } else {
+        hungarian(num_cols, num_rows, |c, r| weights[r][c])
+            .into_iter()
+            .map(|(c, r)| (r, c))
+            .collect()
+    }
+}
+
+// Hungarian algorithm for n <= m, O(n^2 m)
+//  (https://cp-algorithms.com/graph/hungarian-algorithm.html, negated to maximise)
+fn hungarian(n: usize, m: usize, weight: impl Fn(usize, usize) -> f32) -> Vec<(usize, usize)> {
+    // everything here is 1-indexed, with 0 as a dummy row/column
+    let cost = |i: usize, j: usize| -(weight(i - 1, j - 1) as f64);
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; m + 1];
+    // row assigned to each column
+    let mut p = vec![0; m + 1];
+    let mut way = vec![0; m + 1];
+
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=m {
+                if !used[j] {
+                    let cur = cost(i0, j) - u[i0] - v[j];
+                    if cur < minv[j] {
+                        minv[j] = cur;
+                        way[j] = j0;
+                    }
+                    if minv[j] < delta {
+                        delta = minv[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+        // follow the augmenting path back
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    (1..=m)
+        .filter(|&j| p[j] != 0)
+        .map(|j| (p[j] - 1, j - 1))
+        .collect()
+}
@@ -1,60 +1,136 @@
-#[derive(Copy, Clone)]
+use regex::Regex;
+
+use super::Field;
+
+// A command to change the player's previous guess rather than guess again, e.g.
+//  `.a text` append, `.p text` prepend, `.s old new` or `.s/old/new` substitute,
+//  `.r/pattern/replacement/` regex substitute and `.u` to undo the last edit.
+// The command letter can be followed by a target to only edit one thing:
+//  `n` for the song name, `a` for the anime or a number for that artist (from 1),
+//  e.g. `.s2 Lisa LiSA`. Appended and prepended text can start with anything,
+//  so there the target needs a `:` after it, e.g. `.an: (TV Size)`
 pub(crate) enum EditCommand<'a> {
+    // target, or everything if none
+    Edit(Option<Field>, Edit<'a>),
+    Undo,
+    // no command
+    None,
+}
+
+pub(crate) enum Edit<'a> {
     // text to append
     Append(&'a str),
     // old, new
     Substitute(&'a str, &'a str),
+    // pattern, replacement
+    Regex(Regex, &'a str),
     // text to prepend
     Prepend(&'a str),
-    // no command
-    None,
 }
 
 impl<'a> EditCommand<'a> {
     pub fn new(s: &'a str) -> Self {
-        let mut edit = EditCommand::None;
-        if let Some(command) = s.strip_prefix(".") {
-            match command.get(0..1) {
-                // append
-                Some("a") => {
-                    edit = EditCommand::Append(&command[1..]);
-                }
-                // substitute
-                Some("s") => {
-                    let text = command[1..].trim_start();
-                    let (delim, text) = if let Some(rest) = text.strip_prefix('/') {
-                        ('/', rest)
-                    } else {
-                        (' ', text)
-                    };
-                    if let Some((old, new)) = text.split_once(delim) {
-                        edit = EditCommand::Substitute(old, new);
-                    }
-                }
-                // prepend
-                Some("p") => {
-                    edit = EditCommand::Prepend(&command[1..]);
-                }
-                _ => {}
+        let Some(command) = s.strip_prefix('.') else {
+            return EditCommand::None;
+        };
+        let Some(letter) = command.get(0..1) else {
+            return EditCommand::None;
+        };
+        let rest = &command[1..];
+        let edit = match letter {
+            // append
+            "a" => {
+                let (target, text) = text_target(rest);
+                Some((target, Edit::Append(text)))
+            }
+            // substitute
+            "s" => {
+                let (target, text) = target(rest);
+                let text = text.trim_start();
+                let (delim, text) = if let Some(rest) = text.strip_prefix('/') {
+                    ('/', rest)
+                } else {
+                    (' ', text)
+                };
+                text.split_once(delim)
+                    .map(|(old, new)| (target, Edit::Substitute(old, new)))
             }
+            // regex substitute, the pattern can't contain `/`
+            "r" => {
+                let (target, text) = target(rest);
+                text.trim_start()
+                    .strip_prefix('/')
+                    .and_then(|text| text.split_once('/'))
+                    .and_then(|(pattern, new)| {
+                        let new = new.strip_suffix('/').unwrap_or(new);
+                        Some((target, Edit::Regex(Regex::new(pattern).ok()?, new)))
+                    })
+            }
+            // prepend
+            "p" => {
+                let (target, text) = text_target(rest);
+                Some((target, Edit::Prepend(text)))
+            }
+            "u" if rest.trim().is_empty() => return EditCommand::Undo,
+            _ => None,
+        };
+        match edit {
+            Some((target, edit)) => EditCommand::Edit(target, edit),
+            None => EditCommand::None,
         }
-        edit
     }
+}
 
-    pub fn apply(&self, target: &str) -> Option<String> {
+// Splits off the target at the start of a substitution, if there is one.
+// It has to be followed by a space or `/`, e.g. `.sn old new` or `.r2/a/b/`
+fn target(text: &str) -> (Option<Field>, &str) {
+    let Some(end) = text.find(|c: char| c.is_whitespace() || c == '/') else {
+        return (None, text);
+    };
+    match field(&text[..end]) {
+        Some(field) => (Some(field), &text[end..]),
+        None => (None, text),
+    }
+}
+
+// Splits off a target followed by `:`, so that e.g. `.an text` still appends "n text"
+fn text_target(text: &str) -> (Option<Field>, &str) {
+    let Some((name, rest)) = text.split_once(':') else {
+        return (None, text);
+    };
+    match field(name) {
+        Some(field) => (Some(field), rest),
+        None => (None, text),
+    }
+}
+
+fn field(name: &str) -> Option<Field> {
+    match name {
+        "n" => Some(Field::SongName),
+        "a" => Some(Field::Anime),
+        number => number
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .map(Field::Artist),
+    }
+}
+
+impl<'a> Edit<'a> {
+    pub fn apply(&self, target: &str) -> String {
         match self {
-            EditCommand::Append(x) => Some(format!("{}{}", target, x)),
-            EditCommand::Substitute(x, y) => Some(target.replace(x, y)),
+            Edit::Append(x) => format!("{}{}", target, x),
+            Edit::Substitute(x, y) => target.replace(x, y),
+            Edit::Regex(re, y) => re.replace_all(target, *y).into_owned(),
             // prepend is a bit different
             //  if it starts with a space, remove and add it at the end
-            EditCommand::Prepend(x) => Some({
+            Edit::Prepend(x) => {
                 if let Some(x) = x.strip_prefix(' ') {
                     format!("{} {}", x, target)
                 } else {
                     format!("{}{}", x, target)
                 }
-            }),
-            EditCommand::None => None,
+            }
         }
     }
 }
@@ -0,0 +1,114 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::quiz::SongArtistQuiz;
+
+// Each hint after the first letters reveals this fraction of the other letters
+const REVEAL_STEPS: usize = 4;
+
+// Masked answers for the current song, revealing more with each hint:
+//  1. the number of words and their lengths
+//  2. the first letter of each word
+//  3. and on, more letters at random
+#[derive(Debug, Clone)]
+pub struct Hint {
+    // number of hints given for this song, including this one
+    pub level: usize,
+    pub song_name: Option<String>,
+    pub artist: Option<String>,
+    pub anime: Option<String>,
+}
+
+// Answers to give hints for, and when hints were given
+pub(crate) struct Hints {
+    pub song_name: Option<String>,
+    pub artist: Option<String>,
+    pub anime: Option<String>,
+    // so the same letters get revealed each time
+    pub seed: u64,
+    // times in seconds that hints were given
+    pub times: Vec<f32>,
+}
+
+impl Hints {
+    pub(crate) fn new(
+        song_name: Option<String>,
+        artist: Option<String>,
+        anime: Option<String>,
+    ) -> Self {
+        Hints {
+            song_name,
+            artist,
+            anime,
+            seed: rand::random(),
+            times: Vec::new(),
+        }
+    }
+
+    // Number of hints given at or before the time
+    pub(crate) fn given_before(&self, time: f32) -> usize {
+        self.times.iter().filter(|t| **t <= time).count()
+    }
+
+    fn next(&mut self, time: f32) -> Hint {
+        self.times.push(time);
+        let level = self.times.len();
+        let mask = |s: &Option<String>| s.as_deref().map(|s| mask(s, level, self.seed));
+        Hint {
+            level,
+            song_name: mask(&self.song_name),
+            artist: mask(&self.artist),
+            anime: mask(&self.anime),
+        }
+    }
+}
+
+// Hides the letters and digits of the answer with `_`, keeping spaces and punctuation
+fn mask(answer: &str, level: usize, seed: u64) -> String {
+    let mut chars: Vec<(char, bool)> = answer.chars().map(|c| (c, !c.is_alphanumeric())).collect();
+
+    if level >= 2 {
+        // first letter of each word
+        let mut word_start = true;
+        for (c, shown) in chars.iter_mut() {
+            if c.is_whitespace() {
+                word_start = true;
+            } else if c.is_alphanumeric() && word_start {
+                *shown = true;
+                word_start = false;
+            }
+        }
+    }
+
+    if level >= 3 {
+        let mut hidden: Vec<usize> = (0..chars.len()).filter(|&i| !chars[i].1).collect();
+        hidden.shuffle(&mut StdRng::seed_from_u64(seed));
+        // always leave a letter for them to guess
+        let per_step = hidden.len().div_ceil(REVEAL_STEPS);
+        let reveal = ((level - 2) * per_step).min(hidden.len().saturating_sub(1));
+        for &i in &hidden[..reveal] {
+            chars[i].1 = true;
+        }
+    }
+
+    chars
+        .into_iter()
+        .map(|(c, shown)| if shown { c } else { '_' })
+        .collect()
+}
+
+impl SongArtistQuiz {
+    // Gives the next hint for the current song, `time` seconds into the song
+    pub fn hint(&self, time: f32) -> Option<Hint> {
+        let mut guard = self.curr_info.lock().expect("mutex poisoned");
+        let info = guard.as_mut()?;
+        Some(info.hints.next(time))
+    }
+
+    // Number of hints given for the current song
+    pub fn hints_used(&self) -> usize {
+        let guard = self.curr_info.lock().expect("mutex poisoned");
+        guard.as_ref().map_or(0, |info| info.hints.times.len())
+    }
+}
@@ -1,45 +1,88 @@
-use std::collections::BTreeMap;
-use std::sync::atomic::{AtomicI64, Ordering};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use crate::Error;
 
+mod choice;
+mod details;
+mod explain;
 pub(crate) mod guess;
+mod hint;
+mod romaji;
+mod rules;
+mod score;
+mod scorer;
 mod settings;
+mod snapshot;
+mod team;
 
+pub use choice::Choice;
+use choice::Choices;
+pub use details::{Season, SongType, SongTypeKind, Vintage};
+pub use explain::{Diff, Explanation, GuessExplanation};
+use guess::FieldEdit;
 pub use guess::Guess;
-pub use settings::GuessSettings;
+pub use hint::Hint;
+use hint::Hints;
+pub use rules::{Rule, RuleSet, Rules};
+pub use score::{PlayerScore, ScorePart, ScoreRule, ScoreSettings};
+use score::{Scoreboard, Standings};
+pub use scorer::{DamerauLevenshtein, JaroWinkler, LengthAdaptive, Scorer, ScorerKind, TokenSet};
+pub use settings::{GuessSettings, GuessTargets};
+use settings::{Name, NormalisedGuesses};
+pub use snapshot::{Snapshot, SNAPSHOT_VERSION};
+pub use team::Teams;
+
+// Past songs kept for review by default, so long quizzes don't keep every song
+pub const DEFAULT_HISTORY_LEN: usize = 100;
 
 // TODO: maybe consider removing the Arcs/Mutexes from here
 //  and making the user care about that
 #[derive(Clone)]
 pub struct SongArtistQuiz {
     song_number: Arc<AtomicI64>,
+    // whether the current song has finished playing
+    song_finished: Arc<AtomicBool>,
+    // info for the current song, songs loaded ahead and recent past songs
     song_info: Arc<Mutex<BTreeMap<i64, SongInfo>>>,
+    // number of past songs to keep info for
+    history_len: Arc<AtomicUsize>,
     curr_info: Arc<Mutex<Option<GuessInfo>>>,
     guess_settings: Arc<Mutex<GuessSettings>>,
     song_start_time: Arc<Mutex<Instant>>,
+    // points from finished songs
+    scoreboard: Arc<Mutex<Scoreboard>>,
+    teams: Arc<Mutex<Teams>>,
 }
 
 impl SongArtistQuiz {
     pub fn new() -> Self {
         SongArtistQuiz {
             song_number: Arc::new(AtomicI64::new(0)),
+            song_finished: Arc::new(AtomicBool::new(false)),
             song_info: Arc::new(Mutex::new(BTreeMap::new())),
+            history_len: Arc::new(AtomicUsize::new(DEFAULT_HISTORY_LEN)),
             curr_info: Arc::new(Mutex::new(None)),
             guess_settings: Arc::new(Mutex::new(GuessSettings::default())),
             song_start_time: Arc::new(Mutex::new(Instant::now())),
+            scoreboard: Arc::new(Mutex::new(Scoreboard::default())),
+            teams: Arc::new(Mutex::new(Teams::default())),
         }
     }
 
     pub fn new_with_settings(settings: GuessSettings) -> Self {
         SongArtistQuiz {
             song_number: Arc::new(AtomicI64::new(0)),
+            song_finished: Arc::new(AtomicBool::new(false)),
             song_info: Arc::new(Mutex::new(BTreeMap::new())),
+            history_len: Arc::new(AtomicUsize::new(DEFAULT_HISTORY_LEN)),
             curr_info: Arc::new(Mutex::new(None)),
             guess_settings: Arc::new(Mutex::new(settings)),
             song_start_time: Arc::new(Mutex::new(Instant::now())),
+            scoreboard: Arc::new(Mutex::new(Scoreboard::default())),
+            teams: Arc::new(Mutex::new(Teams::default())),
         }
     }
 
@@ -48,7 +91,10 @@ impl SongArtistQuiz {
         *guard = None;
         let mut guard = self.song_info.lock().expect("mutex poisoned");
         guard.clear();
+        let mut guard = self.scoreboard.lock().expect("mutex poisoned");
+        guard.clear();
         self.song_number.store(0, Ordering::Release);
+        self.song_finished.store(false, Ordering::Release);
     }
 
     pub fn curr_song_number(&self) -> i64 {
@@ -56,28 +102,75 @@ impl SongArtistQuiz {
     }
 
     pub fn set_song_number(&self, num: i64) -> Option<SongInfo> {
-        let map = self.song_info.lock().expect("mutex poisoned");
+        let mut map = self.song_info.lock().expect("mutex poisoned");
+        // forget songs that have fallen out of the history
+        let oldest = self.oldest_kept(num);
+        map.retain(|&n, _| n >= oldest);
         let info = map.get(&num);
         let mut guard = self.curr_info.lock().expect("mutex poisoned");
         let settings = self.guess_settings.lock().expect("mutex poisoned").clone();
-        *guard = info.and_then(|s| GuessInfo::from_song_info(s, settings));
+        let old = std::mem::replace(
+            &mut *guard,
+            info.and_then(|s| GuessInfo::from_song_info(s, settings)),
+        );
+        // Add the points from the last song
+        if let Some(old) = old {
+            let mut scoreboard = self.scoreboard.lock().expect("mutex poisoned");
+            scoreboard.add_song(&old);
+        }
         self.song_number.store(num, Ordering::Release);
+        self.song_finished.store(false, Ordering::Release);
         info.cloned()
     }
 
+    // Marks the current song as finished, so it can be explained
+    pub fn finish_song(&self) {
+        self.song_finished.store(true, Ordering::Release);
+    }
+
+    // The current song once it has finished, otherwise the one before it
+    pub fn last_finished_song(&self) -> i64 {
+        let curr = self.curr_song_number();
+        if self.song_finished.load(Ordering::Acquire) {
+            curr
+        } else {
+            curr - 1
+        }
+    }
+
     pub fn set_info(&self, song_number: i64, info: SongInfo) -> Result<(), Error> {
-        // TODO: allow more than 100 songs, depending on where used
-        if song_number < 0 || song_number > 100 {
+        // it would be forgotten straight away
+        if song_number < 0 || song_number < self.oldest_kept(self.curr_song_number()) {
             return Err(Error::SongNumberOutOfRange(song_number));
         }
         let mut map = self.song_info.lock().expect("mutex poisoned");
         if let Some(old) = map.insert(song_number, info) {
-            return Err(Error::DuplicateSongInfoReceived(old));
+            return Err(Error::DuplicateSongInfoReceived(Box::new(old)));
         }
 
         Ok(())
     }
 
+    // Number of past songs to keep info for, 0 to only keep the current song
+    pub fn set_history_len(&self, len: usize) {
+        self.history_len.store(len, Ordering::Release);
+    }
+
+    // Info for the past songs still kept, oldest first
+    pub fn past_songs(&self) -> Vec<(i64, SongInfo)> {
+        let curr = self.curr_song_number();
+        let map = self.song_info.lock().expect("mutex poisoned");
+        map.range(..curr)
+            .map(|(&n, info)| (n, info.clone()))
+            .collect()
+    }
+
+    // Oldest song number kept when the song number is `curr`
+    fn oldest_kept(&self, curr: i64) -> i64 {
+        let len = self.history_len.load(Ordering::Acquire);
+        curr.saturating_sub(i64::try_from(len).unwrap_or(i64::MAX))
+    }
+
     pub fn set_song_timer(&self, time: f64) {
         let now = Instant::now();
         let mut guard = self.song_start_time.lock().expect("mutex poisoned");
@@ -93,9 +186,22 @@ impl SongArtistQuiz {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum SongInfo {
-    Info { song_name: String, artist: String },
+    Info {
+        song_name: String,
+        artist: String,
+        // every known name for the anime (romaji, English and alternate names)
+        anime_names: Vec<String>,
+        // e.g. "Opening 2"
+        song_type: Option<String>,
+        // e.g. "Fall 2019"
+        vintage: Option<String>,
+        // wrong answers to offer alongside the song when picking from choices
+        distractors: Vec<Choice>,
+        // (artist, alias) for the credited artists of just this song
+        artist_aliases: Vec<(String, String)>,
+    },
     Undefined,
     NoCatboxLinks,
 }
@@ -103,9 +209,25 @@ pub enum SongInfo {
 impl From<Option<database::SongInfo>> for SongInfo {
     fn from(value: Option<database::SongInfo>) -> Self {
         if let Some(v) = value {
+            // these come from the `song_type`/`type_number` (or combined `type`)
+            //  and `vintage` columns of a quiz query
+            let field = |name: &str| match v.fields.get(name) {
+                Some(database::Value::String(s)) => Some(s.clone()),
+                _ => None,
+            };
+            let song_type = match (&v.song_type, v.type_number) {
+                (Some(ty), Some(num)) => Some(format!("{} {}", ty, num)),
+                (Some(ty), None) => Some(ty.clone()),
+                (None, _) => field("type"),
+            };
             SongInfo::Info {
+                song_type,
+                vintage: field("vintage"),
                 song_name: v.song_name,
                 artist: v.artist,
+                anime_names: v.anime_names,
+                distractors: Vec::new(),
+                artist_aliases: Vec::new(),
             }
         } else {
             SongInfo::Undefined
@@ -113,19 +235,59 @@ impl From<Option<database::SongInfo>> for SongInfo {
     }
 }
 
-// Current best guess for song name and each artist
+// Current best guess for song name, each artist, anime and details,
+//  with `None`/no artists for anything that isn't being guessed
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
 struct BestGuess {
-    song_name: Guess,
+    song_name: Option<Guess>,
     artists: Vec<Guess>,
+    anime: Option<Guess>,
+    song_type: Option<Guess>,
+    vintage: Option<Guess>,
+    // the option picked, when picking from choices
+    choice: Option<Guess>,
+    // fields changed by the player's edits since their last new guess,
+    //  most recent last, for further edits and undo
+    edits: Vec<Vec<FieldEdit>>,
+}
+
+impl BestGuess {
+    // Every guess being made, for scoring
+    fn guesses(&self) -> impl Iterator<Item = &Guess> {
+        self.song_name
+            .iter()
+            .chain(self.artists.iter())
+            .chain(self.anime.iter())
+            .chain(self.song_type.iter())
+            .chain(self.vintage.iter())
+            .chain(self.choice.iter())
+    }
+
+    // Whether everything has been guessed, if there is anything to guess
+    fn correct(&self) -> bool {
+        let mut guesses = self.guesses().peekable();
+        guesses.peek().is_some() && guesses.all(Guess::correct)
+    }
 }
 
 struct GuessInfo {
-    song_name: String,
-    // artist order (last first, first last) - TODO: extend this to nicknames, just have a list of names that work
-    artists: Vec<(String, Option<String>)>,
+    // forms of the song name accepted (with and without optional parts)
+    song_name: Option<Vec<Name>>,
+    // names accepted for each artist (as written, last name first, and any aliases)
+    artists: Vec<Vec<Name>>,
+    // forms of every name the anime is known by
+    anime: Option<Vec<Name>>,
+    song_type: Option<SongType>,
+    vintage: Option<Vintage>,
+    choices: Option<Choices>,
+    hints: Hints,
     // Combined best guess from anyone for song name/artist
     global_best_guess: BestGuess,
-    // player_guesses: HashMap<String, BestGuess>,
+    // Best guesses from each player
+    player_guesses: HashMap<String, BestGuess>,
+    // Best guesses from each team, from any of its members
+    team_guesses: HashMap<String, BestGuess>,
+    normalised: NormalisedGuesses,
     settings: GuessSettings,
 }
 
@@ -134,25 +296,90 @@ impl GuessInfo {
         if let SongInfo::Info {
             ref song_name,
             ref artist,
+            ref anime_names,
+            ref song_type,
+            ref vintage,
+            ref distractors,
+            ref artist_aliases,
         } = info
         {
-            let song_name = settings.normalise_sn(song_name);
+            let settings = settings.with_song_aliases(artist_aliases);
+            let targets = settings.targets;
+            let hints = Hints::new(
+                Some(song_name.clone()).filter(|_| targets.song_name),
+                Some(artist.clone()).filter(|_| targets.artist),
+                anime_names.first().cloned().filter(|_| targets.anime),
+            );
+            // with nothing but the answer to pick, the song isn't guessed by picking
+            let choices = targets
+                .choice
+                .then(|| {
+                    let answer = Choice {
+                        song_name: song_name.clone(),
+                        artist: artist.clone(),
+                    };
+                    Choices::new(answer, distractors)
+                })
+                .filter(|c| c.options.len() > 1);
 
-            let artists = settings.normalise_artists(artist);
+            let song_name = targets.song_name.then(|| settings.normalise_sn(song_name));
 
-            let global_best_guess = BestGuess {
-                song_name: Guess::default(),
-                artists: vec![Guess::default(); artists.len()],
+            let artists = if targets.artist {
+                settings.normalise_artists(artist)
+            } else {
+                Vec::new()
             };
 
-            Some(GuessInfo {
+            // can't guess the anime without knowing any of its names
+            let anime = (targets.anime && !anime_names.is_empty()).then(|| {
+                anime_names
+                    .iter()
+                    .flat_map(|n| settings.normalise_sn(n))
+                    .collect()
+            });
+
+            let song_type = song_type
+                .as_deref()
+                .filter(|_| targets.song_type)
+                .and_then(SongType::parse);
+            let vintage = vintage
+                .as_deref()
+                .filter(|_| targets.vintage)
+                .and_then(Vintage::parse);
+
+            let mut info = GuessInfo {
                 song_name,
                 artists,
-                global_best_guess,
+                anime,
+                song_type,
+                vintage,
+                choices,
+                hints,
+                global_best_guess: BestGuess::default(),
+                player_guesses: HashMap::new(),
+                team_guesses: HashMap::new(),
+                normalised: NormalisedGuesses::default(),
                 settings,
-            })
+            };
+            info.global_best_guess = info.new_best_guess();
+            // everyone gets to pick, so one right pick doesn't end the song
+            info.global_best_guess.choice = None;
+            Some(info)
         } else {
             None
         }
     }
+
+    // Empty guesses for everything being guessed this song
+    fn new_best_guess(&self) -> BestGuess {
+        BestGuess {
+            song_name: self.song_name.as_ref().map(|_| Guess::default()),
+            artists: vec![Guess::default(); self.artists.len()],
+            anime: self.anime.as_ref().map(|_| Guess::default()),
+            song_type: self.song_type.map(|_| Guess::default()),
+            vintage: self.vintage.map(|_| Guess::default()),
+            choice: self.choices.as_ref().map(|_| Guess::default()),
+            edits: Vec::new(),
+        }
+    }
 }
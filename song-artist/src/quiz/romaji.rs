@@ -0,0 +1,378 @@
+// Transliteration of kana to romaji so that guesses can be compared
+//  regardless of which script (or romanisation system) they were typed in.
+// This expects text that has already been normalised, so katakana has already
+//  been folded to hiragana and voiced kana are composed.
+
+// Converts any kana in the string to (Hepburn) romaji and then canonicalises
+//  it, along with any latin words that are already romaji, so that the common
+//  romanisation variants compare equal. Other latin text is left as is
+pub(crate) fn romanise(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 2);
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let script = Script::of(c);
+        let end = rest.find(|c| Script::of(c) != script).unwrap_or(rest.len());
+        let (run, next) = rest.split_at(end);
+        match script {
+            Script::Kana => out.push_str(&canonicalise(&kana_to_romaji(run))),
+            Script::Latin if is_romaji(run) => out.push_str(&canonicalise(run)),
+            _ => out.push_str(run),
+        }
+        rest = next;
+    }
+    out
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Kana,
+    Latin,
+    Other,
+}
+
+impl Script {
+    fn of(c: char) -> Script {
+        if kana(c).is_some()
+            || matches!(
+                c,
+                'っ' | 'ゃ' | 'ゅ' | 'ょ' | 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ'
+            )
+            || matches!(c, 'ー' | 'ゝ' | 'ゞ' | '\u{3099}' | '\u{309A}')
+        {
+            Script::Kana
+        } else if c.is_ascii_lowercase() {
+            Script::Latin
+        } else {
+            Script::Other
+        }
+    }
+}
+
+// Whether a latin word can be read as a sequence of romaji syllables (in any
+//  romanisation system), rather than being e.g. english that happens to contain "sh"
+fn is_romaji(word: &str) -> bool {
+    let b = word.as_bytes();
+    // whether the first i letters split into syllables
+    let mut reachable = vec![false; b.len() + 1];
+    reachable[0] = true;
+    for i in 0..b.len() {
+        if reachable[i] {
+            for len in syllable_lens(&b[i..]).into_iter().flatten() {
+                reachable[i + len] = true;
+            }
+        }
+    }
+    reachable[b.len()]
+}
+
+// Lengths of the romaji syllables that the text could start with
+fn syllable_lens(s: &[u8]) -> [Option<usize>; 4] {
+    const ONSETS: &[&str] = &[
+        "k", "g", "s", "z", "t", "d", "n", "h", "b", "p", "m", "r", "y", "w", "f", "j", "v", "sh",
+        "ch", "ts", "dz", "ky", "gy", "sy", "zy", "ty", "dy", "ny", "hy", "by", "py", "my", "ry",
+        "jy",
+    ];
+    let vowel_at = |i: usize| s.get(i).is_some_and(|&c| is_vowel(c as char));
+    let mut lens = [None; 4];
+    if vowel_at(0) {
+        lens[0] = Some(1);
+    }
+    match s {
+        // syllabic n, written m before labials
+        [b'n', ..] | [b'm', b'b' | b'p', ..] => lens[1] = Some(1),
+        // doubled consonant (sokuon)
+        [a, b, ..] if a == b && !is_vowel(*a as char) && *a != b'n' => lens[1] = Some(1),
+        [b't', b'c', ..] => lens[1] = Some(1),
+        _ => {}
+    }
+    for (slot, onset) in lens[2..].iter_mut().zip([1, 2]) {
+        let fits = s.len() > onset
+            && ONSETS.iter().any(|o| o.as_bytes() == &s[..onset])
+            && vowel_at(onset);
+        if fits {
+            *slot = Some(onset + 1);
+        }
+    }
+    lens
+}
+
+fn kana_to_romaji(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 2);
+    // start of the romaji for the last kana written, for the small kana to modify
+    let mut last: Option<usize> = None;
+    // whether the next kana should have its consonant doubled (っ)
+    let mut sokuon = false;
+
+    for c in s.chars() {
+        if let Some(romaji) = kana(c) {
+            if sokuon {
+                // っち is written as tchi in Hepburn
+                if romaji.starts_with("ch") {
+                    out.push('t');
+                } else if let Some(first) = romaji.chars().next().filter(|c| !is_vowel(*c)) {
+                    out.push(first);
+                }
+                sokuon = false;
+            }
+            last = Some(out.len());
+            out.push_str(romaji);
+            continue;
+        }
+        match c {
+            'っ' => {
+                sokuon = true;
+                continue;
+            }
+            // small ya, yu, yo combine with the previous i-row kana: きゃ -> kya, しゃ -> sha
+            'ゃ' | 'ゅ' | 'ょ' => {
+                let vowel = match c {
+                    'ゃ' => 'a',
+                    'ゅ' => 'u',
+                    _ => 'o',
+                };
+                match last.map(|i| &out[i..]) {
+                    Some(prev) if prev.ends_with('i') && prev.len() > 1 => {
+                        let palatal = matches!(prev, "shi" | "chi" | "ji");
+                        out.pop();
+                        if !palatal {
+                            out.push('y');
+                        }
+                        out.push(vowel);
+                    }
+                    _ => {
+                        out.push('y');
+                        out.push(vowel);
+                    }
+                }
+            }
+            // small vowels replace the vowel of the previous kana: ふぁ -> fa, てぃ -> ti
+            'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' => {
+                let vowel = match c {
+                    'ぁ' => 'a',
+                    'ぃ' => 'i',
+                    'ぅ' => 'u',
+                    'ぇ' => 'e',
+                    _ => 'o',
+                };
+                if let Some(i) = last {
+                    if out.ends_with(is_vowel) {
+                        out.pop();
+                    }
+                    // うぃ -> wi
+                    if out.len() == i {
+                        out.push('w');
+                    }
+                }
+                out.push(vowel);
+            }
+            // long vowel mark repeats the previous vowel
+            'ー' => {
+                if let Some(v) = out.chars().last().filter(|c| is_vowel(*c)) {
+                    out.push(v);
+                }
+            }
+            // iteration marks repeat the previous kana
+            'ゝ' | 'ゞ' => {
+                if let Some(i) = last {
+                    let prev = out[i..].to_string();
+                    last = Some(out.len());
+                    out.push_str(&prev);
+                }
+            }
+            // stray voicing marks that couldn't be composed
+            '\u{3099}' | '\u{309A}' => {}
+            c => {
+                last = None;
+                out.push(c);
+            }
+        }
+        sokuon = false;
+    }
+    out
+}
+
+// Collapses the differences between romanisation systems and the ways
+//  long vowels get written (ou/oo/ō/o), so that e.g. "shinzou wo sasageyo"
+//  and "sinzo wo sasageyo" end up the same.
+fn canonicalise(s: &str) -> String {
+    const REPLACEMENTS: &[(&str, &str)] = &[
+        // Hepburn to Kunrei
+        ("sh", "sy"),
+        ("ch", "ty"),
+        ("tsu", "tu"),
+        ("fu", "hu"),
+        ("j", "zy"),
+        ("dzu", "zu"),
+        ("syi", "si"),
+        ("tyi", "ti"),
+        ("zyi", "zi"),
+        // long vowels
+        ("ou", "o"),
+        ("oo", "o"),
+        ("uu", "u"),
+        ("aa", "a"),
+        ("ee", "e"),
+        ("ii", "i"),
+        // syllabic n before labials: shimbun, shinbun
+        ("mb", "nb"),
+        ("mp", "np"),
+    ];
+    let mut s = s.to_string();
+    for (from, to) in REPLACEMENTS {
+        if s.contains(from) {
+            s = s.replace(from, to);
+        }
+    }
+    s
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+// Hepburn romaji for a single (hiragana) kana
+fn kana(c: char) -> Option<&'static str> {
+    let romaji = match c {
+        'あ' => "a",
+        'い' => "i",
+        'う' => "u",
+        'え' => "e",
+        'お' => "o",
+        'か' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' => "ke",
+        'こ' => "ko",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'ざ' => "za",
+        'じ' => "ji",
+        'ず' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'だ' => "da",
+        'ぢ' => "ji",
+        'づ' => "zu",
+        'で' => "de",
+        'ど' => "do",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' => "ya",
+        'ゆ' => "yu",
+        'よ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' => "wa",
+        'ゐ' => "i",
+        'ゑ' => "e",
+        'を' => "wo",
+        'ん' => "n",
+        'ゔ' => "vu",
+        _ => return None,
+    };
+    Some(romaji)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GuessSettings;
+
+    #[test]
+    fn romaji_words() {
+        for word in [
+            "shinzouwosasageyo",
+            "sinzo",
+            "tchotto",
+            "shimbun",
+            "kyouka",
+            "fuji",
+        ] {
+            assert!(is_romaji(word), "{word}");
+        }
+        for word in [
+            "shoot",
+            "judge",
+            "charge",
+            "crossingfield",
+            "unravel",
+            "street",
+        ] {
+            assert!(!is_romaji(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn romanisation_variants_match() {
+        let settings = GuessSettings::default();
+        let score = |guess, answer| settings.score_song_name(guess, answer);
+        assert_eq!(score("sinzo wo sasageyo", "Shinzou wo Sasageyo"), 1.0);
+        assert_eq!(score("shinbun", "shimbun"), 1.0);
+        assert_eq!(score("しんぞうをささげよ", "Shinzou wo Sasageyo"), 1.0);
+        assert_eq!(score("シンゾウヲササゲヨ", "sinzoo wo sasageyo"), 1.0);
+    }
+
+    #[test]
+    fn english_titles_score_unchanged() {
+        let transliterated = GuessSettings::default();
+        let plain = GuessSettings {
+            transliterate: false,
+            ..GuessSettings::default()
+        };
+        let pairs = [
+            ("shoot", "soot"),
+            ("judge", "zyudge"),
+            ("charge", "tyarge"),
+            ("Crossing Field", "Crossing Fields"),
+            ("Unravel", "Unrabel"),
+            ("Butter-Fly", "Butterfly"),
+            ("Again", "Agian"),
+        ];
+        for (guess, answer) in pairs {
+            assert_eq!(
+                transliterated.score_song_name(guess, answer),
+                plain.score_song_name(guess, answer),
+                "{guess} / {answer}"
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use regex::{NoExpand, Regex};
+
+use crate::Error;
+
+// A rule applied to the raw text of both the answers and the guesses, before normalising
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Rule {
+    // parts matching the pattern can be left out, e.g. `\s*\(TV Size\)` or ` feat\. .*`
+    Optional { pattern: String },
+    // parts matching the pattern are always removed, e.g. `\s*-movie ver\.-`
+    Strip { pattern: String },
+    // any of these terms count as the same word, e.g. `["and", "&"]` or `["2", "II"]`
+    Equivalent { terms: Vec<String> },
+}
+
+// The rules as written in a rules file
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    // Reads a yaml file with a list of `rules`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<RuleSet, Error> {
+        let text = std::fs::read_to_string(path).map_err(Error::ReadRulesError)?;
+        serde_yaml::from_str(&text).map_err(Error::ParseRulesError)
+    }
+
+    pub fn compile(&self) -> Result<Rules, Error> {
+        let mut rules = Rules::default();
+        for rule in &self.rules {
+            match rule {
+                Rule::Optional { pattern } => rules.optional.push(compile(pattern)?),
+                Rule::Strip { pattern } => rules.strip.push(compile(pattern)?),
+                Rule::Equivalent { terms } => {
+                    let Some(canonical) = terms.first() else {
+                        continue;
+                    };
+                    let alternatives: Vec<_> = terms.iter().map(|t| term_pattern(t)).collect();
+                    let re = compile(&format!("(?i){}", alternatives.join("|")))?;
+                    rules.equivalent.push((re, canonical.clone()));
+                }
+            }
+        }
+        Ok(rules)
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, Error> {
+    Regex::new(pattern).map_err(|e| Error::InvalidRule(pattern.to_string(), e))
+}
+
+// Matches the term as a whole word, as far as the term has word characters at its ends
+//  (so `&` can match inside `A&B`, but `2` won't match inside `25`)
+fn term_pattern(term: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut pattern = regex::escape(term);
+    if is_word(term.chars().next()) {
+        pattern.insert_str(0, r"\b");
+    }
+    if is_word(term.chars().last()) {
+        pattern.push_str(r"\b");
+    }
+    pattern
+}
+
+// Compiled rules, ready to apply to text
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    optional: Vec<Regex>,
+    strip: Vec<Regex>,
+    // pattern matching any of the terms, the term to replace them with
+    equivalent: Vec<(Regex, String)>,
+}
+
+impl Rules {
+    pub fn is_empty(&self) -> bool {
+        self.optional.is_empty() && self.strip.is_empty() && self.equivalent.is_empty()
+    }
+
+    // Descriptions of the rules that change the text
+    pub(crate) fn applied(&self, s: &str) -> Vec<String> {
+        let strip = self
+            .strip
+            .iter()
+            .filter(|re| re.is_match(s))
+            .map(|re| format!("strip `{}`", re.as_str()));
+        let equivalent = self
+            .equivalent
+            .iter()
+            .filter(|(re, canonical)| re.find_iter(s).any(|m| m.as_str() != canonical))
+            .map(|(_, canonical)| format!("same as `{}`", canonical));
+        let optional = self
+            .optional
+            .iter()
+            .filter(|re| re.is_match(s))
+            .map(|re| format!("optional `{}`", re.as_str()));
+        strip.chain(equivalent).chain(optional).collect()
+    }
+
+    // The text with the rules applied, and also without any optional parts if it had some
+    pub(crate) fn variants(&self, s: &str) -> Vec<String> {
+        if self.is_empty() {
+            return vec![s.to_string()];
+        }
+        let mut s = s.to_string();
+        for re in &self.strip {
+            s = re.replace_all(&s, "").into_owned();
+        }
+        for (re, canonical) in &self.equivalent {
+            s = re.replace_all(&s, NoExpand(canonical)).into_owned();
+        }
+        let mut shortened = s.clone();
+        for re in &self.optional {
+            shortened = re.replace_all(&shortened, "").into_owned();
+        }
+        // an answer that is entirely optional still has to be guessed
+        if shortened != s && !shortened.trim().is_empty() {
+            vec![s, shortened]
+        } else {
+            vec![s]
+        }
+    }
+}
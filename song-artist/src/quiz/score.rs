@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use crate::quiz::{BestGuess, GuessInfo, Hints, SongArtistQuiz};
+use crate::Guess;
+
+mod rule;
+
+pub use rule::ScoreRule;
+
+// Breakdown entry for the points lost to hints
+const HINT_PENALTY: &str = "hint-penalty";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ScoreSettings {
+    // how each answer earns points, the points from each are added together
+    //  and any multipliers apply to the total for the song
+    pub rules: Vec<ScoreRule>,
+    // fraction of the points for a partly right song type or vintage
+    pub partial_credit: f32,
+    // fraction of the points lost for each hint given before the answer
+    pub hint_penalty: f32,
+}
+
+impl Default for ScoreSettings {
+    fn default() -> Self {
+        ScoreSettings {
+            rules: vec![
+                ScoreRule::Flat { points: 1.0 },
+                ScoreRule::LinearDecay {
+                    points: 1.0,
+                    time: 20.0,
+                },
+            ],
+            partial_credit: 0.5,
+            hint_penalty: 0.0,
+        }
+    }
+}
+
+// Points from one rule, or lost to hints
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScorePart {
+    pub source: String,
+    pub points: f32,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct PlayerScore {
+    pub player: String,
+    pub points: f32,
+    // number of song names/artists/anime guessed correctly
+    pub num_correct: usize,
+    // where the points came from
+    #[serde(default)]
+    pub breakdown: Vec<ScorePart>,
+}
+
+impl PlayerScore {
+    fn add(&mut self, other: &PlayerScore) {
+        self.points += other.points;
+        self.num_correct += other.num_correct;
+        for part in &other.breakdown {
+            self.add_to_breakdown(&part.source, part.points);
+        }
+    }
+
+    fn add_part(&mut self, source: &str, points: f32) {
+        self.points += points;
+        self.add_to_breakdown(source, points);
+    }
+
+    fn add_to_breakdown(&mut self, source: &str, points: f32) {
+        match self.breakdown.iter_mut().find(|p| p.source == source) {
+            Some(part) => part.points += points,
+            None => self.breakdown.push(ScorePart {
+                source: source.to_string(),
+                points,
+            }),
+        }
+    }
+}
+
+impl BestGuess {
+    // `firsts` is the earliest time each guess was right by anyone,
+    //  `streak` the number of songs in a row before this the player scored in
+    fn score(
+        &self,
+        player: &str,
+        settings: &ScoreSettings,
+        hints: &Hints,
+        firsts: &[Option<f32>],
+        streak: usize,
+    ) -> PlayerScore {
+        let mut score = PlayerScore {
+            player: player.to_string(),
+            ..Default::default()
+        };
+        for (i, guess) in self.guesses().enumerate() {
+            let (credit, time) = match *guess {
+                Guess::Correct(_, time) => (1.0, time),
+                Guess::Partial(_, credit, time) => (credit, time),
+                Guess::Incorrect(..) => continue,
+            };
+            if guess.correct() {
+                score.num_correct += 1;
+            }
+            let first = guess.correct() && firsts.get(i).copied().flatten() == Some(time);
+            let hints = hints.given_before(time) as f32;
+            let penalty = (settings.hint_penalty * hints).min(1.0);
+            for rule in &settings.rules {
+                let points = credit * rule.points(time, first);
+                if points != 0.0 {
+                    score.add_part(rule.name(), points);
+                    if penalty > 0.0 {
+                        score.add_part(HINT_PENALTY, -points * penalty);
+                    }
+                }
+            }
+        }
+
+        let subtotal = score.points;
+        if subtotal > 0.0 {
+            for rule in &settings.rules {
+                let multiplier = rule.multiplier(streak);
+                if multiplier != 1.0 {
+                    score.add_part(rule.name(), subtotal * (multiplier - 1.0));
+                }
+            }
+        }
+        score
+    }
+}
+
+impl GuessInfo {
+    // Scores for each player (or team) that scored any points this song,
+    //  given how many songs in a row each has scored in before
+    pub(crate) fn scores<'a>(
+        &'a self,
+        guesses: &'a HashMap<String, BestGuess>,
+        streaks: &'a HashMap<String, usize>,
+    ) -> impl Iterator<Item = PlayerScore> + 'a {
+        let firsts = first_correct(guesses);
+        guesses
+            .iter()
+            .map(move |(player, best)| {
+                let streak = streaks.get(player).copied().unwrap_or(0);
+                best.score(player, &self.settings.scoring, &self.hints, &firsts, streak)
+            })
+            .filter(|s| s.points > 0.0)
+    }
+}
+
+// Earliest time each guess was right, from anyone
+fn first_correct(guesses: &HashMap<String, BestGuess>) -> Vec<Option<f32>> {
+    let mut firsts = Vec::new();
+    for best in guesses.values() {
+        for (i, guess) in best.guesses().enumerate() {
+            if i >= firsts.len() {
+                firsts.push(None);
+            }
+            if let Guess::Correct(_, time) = *guess {
+                let first = &mut firsts[i];
+                if first.is_none_or(|t| time < t) {
+                    *first = Some(time);
+                }
+            }
+        }
+    }
+    firsts
+}
+
+// Running total of points for each player across songs,
+//  and for each team
+#[derive(Default)]
+pub(crate) struct Scoreboard {
+    pub(crate) players: Standings,
+    pub(crate) teams: Standings,
+}
+
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Standings {
+    pub(crate) totals: HashMap<String, PlayerScore>,
+    // number of songs in a row each has scored in, up to the last song
+    pub(crate) streaks: HashMap<String, usize>,
+}
+
+impl Scoreboard {
+    pub(crate) fn add_song(&mut self, info: &GuessInfo) {
+        let scores = info.scores(&info.player_guesses, &self.players.streaks);
+        let scores: Vec<_> = scores.collect();
+        self.players.add(scores);
+        let scores = info.scores(&info.team_guesses, &self.teams.streaks);
+        let scores: Vec<_> = scores.collect();
+        self.teams.add(scores);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.players = Standings::default();
+        self.teams = Standings::default();
+    }
+}
+
+impl Standings {
+    fn add(&mut self, scores: Vec<PlayerScore>) {
+        // anyone who didn't score this song loses their streak
+        self.streaks
+            .retain(|player, _| scores.iter().any(|s| s.player == *player));
+        for score in scores {
+            *self.streaks.entry(score.player.clone()).or_default() += 1;
+            add_score(&mut self.totals, &score);
+        }
+    }
+}
+
+fn add_score(totals: &mut HashMap<String, PlayerScore>, score: &PlayerScore) {
+    totals
+        .entry(score.player.clone())
+        .or_insert_with(|| PlayerScore {
+            player: score.player.clone(),
+            ..Default::default()
+        })
+        .add(score);
+}
+
+// The totals with the scores for the current song added, highest first
+pub(crate) fn with_song(
+    mut totals: HashMap<String, PlayerScore>,
+    song: Vec<PlayerScore>,
+) -> Vec<PlayerScore> {
+    for score in &song {
+        add_score(&mut totals, score);
+    }
+    let mut scores: Vec<_> = totals.into_values().collect();
+    sort_scores(&mut scores);
+    scores
+}
+
+// Highest points first
+pub(crate) fn sort_scores(scores: &mut [PlayerScore]) {
+    scores.sort_by(|a, b| {
+        b.points
+            .total_cmp(&a.points)
+            .then_with(|| a.player.cmp(&b.player))
+    });
+}
+
+impl SongArtistQuiz {
+    // Points each player has scored in the current song so far,
+    //  with where they came from
+    pub fn song_scores(&self) -> Vec<PlayerScore> {
+        let guard = self.curr_info.lock().expect("mutex poisoned");
+        let scoreboard = self.scoreboard.lock().expect("mutex poisoned");
+        let mut scores: Vec<_> = guard
+            .iter()
+            .flat_map(|info| info.scores(&info.player_guesses, &scoreboard.players.streaks))
+            .collect();
+        sort_scores(&mut scores);
+        scores
+    }
+
+    // Total points for each player, including the current song
+    pub fn scoreboard(&self) -> Vec<PlayerScore> {
+        let totals = {
+            let guard = self.scoreboard.lock().expect("mutex poisoned");
+            guard.players.totals.clone()
+        };
+        with_song(totals, self.song_scores())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(rules: Vec<ScoreRule>) -> ScoreSettings {
+        ScoreSettings {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    fn no_hints() -> Hints {
+        Hints::new(None, None, None)
+    }
+
+    fn song_name(guess: Guess) -> BestGuess {
+        BestGuess {
+            song_name: Some(guess),
+            ..Default::default()
+        }
+    }
+
+    fn correct(time: f32) -> BestGuess {
+        song_name(Guess::Correct("song".to_string(), time))
+    }
+
+    #[test]
+    fn first_correct_bonus_only_for_first() {
+        let settings = settings(vec![ScoreRule::FirstCorrectBonus { points: 1.0 }]);
+        let guesses = HashMap::from([
+            ("a".to_string(), correct(5.0)),
+            ("b".to_string(), correct(3.0)),
+        ]);
+        let firsts = first_correct(&guesses);
+        let score = |player: &str| {
+            guesses[player]
+                .score(player, &settings, &no_hints(), &firsts, 0)
+                .points
+        };
+        assert_eq!(score("a"), 0.0);
+        assert_eq!(score("b"), 1.0);
+    }
+
+    #[test]
+    fn streak_resets_on_miss() {
+        let settings = settings(vec![
+            ScoreRule::Flat { points: 1.0 },
+            ScoreRule::StreakMultiplier {
+                step: 0.5,
+                max: 3.0,
+            },
+        ]);
+        let mut standings = Standings::default();
+        let mut play = |best: Option<BestGuess>| {
+            let streak = standings.streaks.get("a").copied().unwrap_or(0);
+            let scores: Vec<_> = best
+                .map(|b| b.score("a", &settings, &no_hints(), &[], streak))
+                .into_iter()
+                .collect();
+            let points = scores.first().map_or(0.0, |s| s.points);
+            standings.add(scores);
+            points
+        };
+        assert_eq!(play(Some(correct(1.0))), 1.0);
+        assert_eq!(play(Some(correct(1.0))), 1.5);
+        assert_eq!(play(None), 0.0);
+        assert_eq!(play(Some(correct(1.0))), 1.0);
+    }
+
+    #[test]
+    fn partial_credit_with_hint_penalty() {
+        let settings = ScoreSettings {
+            rules: vec![ScoreRule::Flat { points: 2.0 }],
+            partial_credit: 0.5,
+            hint_penalty: 0.25,
+        };
+        let mut hints = no_hints();
+        hints.times = vec![1.0, 2.0, 6.0];
+        let best = BestGuess {
+            song_type: Some(Guess::Partial("op".to_string(), 0.5, 4.0)),
+            ..Default::default()
+        };
+
+        // half the points, less a quarter of them for each of the two hints before it
+        let score = best.score("a", &settings, &hints, &[], 0);
+        assert_eq!(score.points, 0.5);
+        assert_eq!(score.num_correct, 0);
+        assert_eq!(
+            score.breakdown,
+            vec![
+                ScorePart {
+                    source: "flat".to_string(),
+                    points: 1.0
+                },
+                ScorePart {
+                    source: HINT_PENALTY.to_string(),
+                    points: -0.5
+                },
+            ]
+        );
+
+        // never more than all the points
+        let settings = ScoreSettings {
+            hint_penalty: 0.75,
+            ..settings
+        };
+        assert_eq!(best.score("a", &settings, &hints, &[], 0).points, 0.0);
+    }
+}
@@ -0,0 +1,87 @@
+// A way of earning points, a quiz can use several of them together
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "rule", rename_all = "kebab-case")]
+pub enum ScoreRule {
+    // the same points for every correct answer
+    Flat { points: f32 },
+    // points for a correct answer straight away,
+    //  decreasing linearly to nothing at `time` seconds
+    LinearDecay { points: f32, time: f32 },
+    // extra points for the first player to get each answer right
+    FirstCorrectBonus { points: f32 },
+    // multiplies a player's points for a song by `1 + step` for each song in a row
+    //  before it they scored in, up to `max` times the points
+    StreakMultiplier { step: f32, max: f32 },
+}
+
+impl ScoreRule {
+    // name shown in score breakdowns, the same as in configs
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreRule::Flat { .. } => "flat",
+            ScoreRule::LinearDecay { .. } => "linear-decay",
+            ScoreRule::FirstCorrectBonus { .. } => "first-correct-bonus",
+            ScoreRule::StreakMultiplier { .. } => "streak-multiplier",
+        }
+    }
+
+    // Points for an answer guessed `time` seconds in, before any credit or penalty
+    pub(crate) fn points(&self, time: f32, first: bool) -> f32 {
+        match *self {
+            ScoreRule::Flat { points } => points,
+            ScoreRule::LinearDecay { points, time: end } if end > 0.0 => {
+                points * (1.0 - time / end).max(0.0)
+            }
+            ScoreRule::FirstCorrectBonus { points } if first => points,
+            _ => 0.0,
+        }
+    }
+
+    // What the points for a song are multiplied by, after `streak` songs in a row
+    pub(crate) fn multiplier(&self, streak: usize) -> f32 {
+        match *self {
+            ScoreRule::StreakMultiplier { step, max } => {
+                (1.0 + step * streak as f32).min(max).max(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_decay() {
+        let rule = ScoreRule::LinearDecay {
+            points: 2.0,
+            time: 10.0,
+        };
+        assert_eq!(rule.points(0.0, false), 2.0);
+        assert_eq!(rule.points(5.0, false), 1.0);
+        assert_eq!(rule.points(10.0, false), 0.0);
+        assert_eq!(rule.points(15.0, false), 0.0);
+    }
+
+    #[test]
+    fn first_correct_bonus() {
+        let rule = ScoreRule::FirstCorrectBonus { points: 3.0 };
+        assert_eq!(rule.points(4.0, true), 3.0);
+        assert_eq!(rule.points(4.0, false), 0.0);
+        assert_eq!(ScoreRule::Flat { points: 3.0 }.points(4.0, false), 3.0);
+    }
+
+    #[test]
+    fn streak_multiplier() {
+        let rule = ScoreRule::StreakMultiplier {
+            step: 0.5,
+            max: 2.0,
+        };
+        assert_eq!(rule.multiplier(0), 1.0);
+        assert_eq!(rule.multiplier(1), 1.5);
+        assert_eq!(rule.multiplier(2), 2.0);
+        assert_eq!(rule.multiplier(5), 2.0);
+        assert_eq!(ScoreRule::Flat { points: 1.0 }.multiplier(5), 1.0);
+    }
+}
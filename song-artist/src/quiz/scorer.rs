@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::Error;
+
+// Similarity between a (normalised) guess and target, from 0 for nothing
+//  in common to 1 for identical
+pub trait Scorer: Send + Sync {
+    fn score(&self, guess: &str, target: &str) -> f32;
+
+    // Most the score could be for texts of these lengths (in chars) that need
+    //  at least `min_edits` edits to be the same, so that guesses that can't
+    //  score well enough aren't compared in full.
+    // By default every guess is compared
+    fn max_score(&self, _min_edits: usize, _guess_len: usize, _target_len: usize) -> f32 {
+        1.0
+    }
+
+    // The score if it is above `min`, otherwise it can be anything up to `min` (e.g. 0)
+    //  so that the comparison can stop as soon as the guess is too far off.
+    // By default the score is always worked out in full
+    fn score_above(&self, guess: &str, target: &str, _min: f32) -> f32 {
+        self.score(guess, target)
+    }
+
+    // name shown in the quiz settings
+    fn name(&self) -> &str;
+}
+
+// Edit distance over the length of the longer string, allowing transpositions
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DamerauLevenshtein;
+
+impl Scorer for DamerauLevenshtein {
+    fn score(&self, guess: &str, target: &str) -> f32 {
+        strsim::normalized_damerau_levenshtein(guess, target) as f32
+    }
+
+    fn max_score(&self, min_edits: usize, guess_len: usize, target_len: usize) -> f32 {
+        match guess_len.max(target_len) {
+            0 => 1.0,
+            // the same sum as strsim, so a guess with exactly this many edits isn't skipped
+            len => (1.0 - min_edits as f64 / len as f64) as f32,
+        }
+    }
+
+    fn score_above(&self, guess: &str, target: &str, min: f32) -> f32 {
+        let guess: Vec<char> = guess.chars().collect();
+        let target: Vec<char> = target.chars().collect();
+        let len = guess.len().max(target.len());
+        if len == 0 {
+            return 1.0;
+        }
+        match bounded_damerau_levenshtein(&guess, &target, max_edits(min, len)) {
+            Some(distance) => (1.0 - distance as f64 / len as f64) as f32,
+            None => 0.0,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Damerau-Levenshtein"
+    }
+}
+
+// Favours guesses that get the start right, so typos near the end
+//  or a missing subtitle cost less
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JaroWinkler;
+
+impl Scorer for JaroWinkler {
+    fn score(&self, guess: &str, target: &str) -> f32 {
+        strsim::jaro_winkler(guess, target) as f32
+    }
+
+    fn max_score(&self, min_edits: usize, guess_len: usize, target_len: usize) -> f32 {
+        if guess_len == 0 && target_len == 0 {
+            return 1.0;
+        }
+        // the characters that need editing can't match, and at best the rest all do
+        //  with no transpositions and are all a common prefix (strsim doesn't cap it)
+        let matches = guess_len.max(target_len).saturating_sub(min_edits);
+        if matches == 0 {
+            return 0.0;
+        }
+        let (m, g, t) = (matches as f64, guess_len as f64, target_len as f64);
+        let jaro = (m / g + m / t + 1.0) / 3.0;
+        (jaro + 0.1 * m * (1.0 - jaro)).min(1.0) as f32
+    }
+
+    fn name(&self) -> &str {
+        "Jaro-Winkler"
+    }
+}
+
+// Compares the sets of words, so word order and repeated words don't matter.
+// The words in common go first in both, so that only the words that differ
+//  count against the guess.
+// This needs spaces to be kept when normalising (space sensitive) to find the words,
+//  otherwise it's the same as plain Levenshtein
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenSet;
+
+impl Scorer for TokenSet {
+    fn score(&self, guess: &str, target: &str) -> f32 {
+        let words = |s: &str| {
+            let mut words: Vec<String> = s.split_whitespace().map(str::to_string).collect();
+            words.sort_unstable();
+            words.dedup();
+            words
+        };
+        let guess_words = words(guess);
+        let target_words = words(target);
+
+        let (common, guess_rest): (Vec<&str>, Vec<&str>) = guess_words
+            .iter()
+            .map(String::as_str)
+            .partition(|w| target_words.iter().any(|t| t == w));
+        let target_rest = target_words
+            .iter()
+            .map(String::as_str)
+            .filter(|w| !common.contains(w));
+
+        let guess_str = common.iter().copied().chain(guess_rest).collect::<Vec<_>>();
+        let target_str = common
+            .iter()
+            .copied()
+            .chain(target_rest)
+            .collect::<Vec<_>>();
+        strsim::normalized_levenshtein(&guess_str.join(" "), &target_str.join(" ")) as f32
+    }
+
+    fn name(&self) -> &str {
+        "Token set"
+    }
+}
+
+// Edit distance judged against the length of the target, clamped to a range:
+//  a typo in a short name costs less than its share of the name,
+//  and near misses on a long name cost more
+#[derive(Debug, Clone, Copy)]
+pub struct LengthAdaptive {
+    // names shorter than this count each edit as if they were this long
+    pub min_len: usize,
+    // names longer than this count each edit as if they were this long
+    pub max_len: usize,
+}
+
+impl Default for LengthAdaptive {
+    fn default() -> Self {
+        LengthAdaptive {
+            min_len: 8,
+            max_len: 20,
+        }
+    }
+}
+
+impl Scorer for LengthAdaptive {
+    fn score(&self, guess: &str, target: &str) -> f32 {
+        let distance = strsim::damerau_levenshtein(guess, target);
+        if distance == 0 {
+            return 1.0;
+        }
+        let len = guess.chars().count().max(target.chars().count());
+        let len = len
+            .clamp(self.min_len, self.max_len.max(self.min_len))
+            .max(1);
+        (1.0 - distance as f32 / len as f32).max(0.0)
+    }
+
+    fn max_score(&self, min_edits: usize, guess_len: usize, target_len: usize) -> f32 {
+        if min_edits == 0 {
+            return 1.0;
+        }
+        let len = guess_len.max(target_len);
+        let len = len
+            .clamp(self.min_len, self.max_len.max(self.min_len))
+            .max(1);
+        (1.0 - min_edits as f32 / len as f32).max(0.0)
+    }
+
+    fn score_above(&self, guess: &str, target: &str, min: f32) -> f32 {
+        let guess: Vec<char> = guess.chars().collect();
+        let target: Vec<char> = target.chars().collect();
+        let len = guess.len().max(target.len());
+        let len = len
+            .clamp(self.min_len, self.max_len.max(self.min_len))
+            .max(1);
+        match bounded_damerau_levenshtein(&guess, &target, max_edits(min, len)) {
+            Some(0) => 1.0,
+            Some(distance) => (1.0 - distance as f32 / len as f32).max(0.0),
+            None => 0.0,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Length adaptive"
+    }
+}
+
+// Most edits that could still score above `min` when each edit costs `1 / len`,
+//  with one to spare so rounding can't skip a guess that would
+fn max_edits(min: f32, len: usize) -> usize {
+    ((1.0 - min.max(0.0) as f64) * len as f64).floor() as usize + 1
+}
+
+// Damerau-Levenshtein distance, the same as strsim's, or `None` once it's more than `max`.
+// The smallest distance in each row of the table is never less than in the row
+//  before it, so it can stop at the first row where everything is over `max`
+fn bounded_damerau_levenshtein(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    if a.is_empty() || b.is_empty() {
+        return Some(a.len().max(b.len()));
+    }
+
+    // with an extra row and column on each side, for transpositions from the start
+    let width = b.len() + 2;
+    let at = |i: usize, j: usize| i * width + j;
+    let mut distances = vec![0; (a.len() + 2) * width];
+    let far = a.len() + b.len();
+    distances[0] = far;
+    for i in 0..=a.len() {
+        distances[at(i + 1, 0)] = far;
+        distances[at(i + 1, 1)] = i;
+    }
+    for j in 0..=b.len() {
+        distances[at(0, j + 1)] = far;
+        distances[at(1, j + 1)] = j;
+    }
+
+    // last row each character of `a` was in
+    let mut last_row: HashMap<char, usize> = HashMap::new();
+    for i in 1..=a.len() {
+        // last column in this row where the characters were the same
+        let mut last_col = 0;
+        let mut row_min = distances[at(i + 1, 1)];
+        for j in 1..=b.len() {
+            let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let l = last_col;
+            let substitution = if a[i - 1] == b[j - 1] {
+                last_col = j;
+                distances[at(i, j)]
+            } else {
+                distances[at(i, j)] + 1
+            };
+            let distance = substitution
+                .min(distances[at(i, j + 1)] + 1)
+                .min(distances[at(i + 1, j)] + 1)
+                .min(distances[at(k, l)] + (i - k - 1) + 1 + (j - l - 1));
+            distances[at(i + 1, j + 1)] = distance;
+            row_min = row_min.min(distance);
+        }
+        if row_min > max {
+            return None;
+        }
+        last_row.insert(a[i - 1], i);
+    }
+    let distance = distances[at(a.len() + 1, b.len() + 1)];
+    (distance <= max).then_some(distance)
+}
+
+// The built in scorers, for choosing one from a setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScorerKind {
+    DamerauLevenshtein,
+    JaroWinkler,
+    TokenSet,
+    LengthAdaptive,
+}
+
+impl ScorerKind {
+    pub const ALL: [ScorerKind; 4] = [
+        ScorerKind::DamerauLevenshtein,
+        ScorerKind::JaroWinkler,
+        ScorerKind::TokenSet,
+        ScorerKind::LengthAdaptive,
+    ];
+
+    pub fn scorer(self) -> Arc<dyn Scorer> {
+        match self {
+            ScorerKind::DamerauLevenshtein => Arc::new(DamerauLevenshtein),
+            ScorerKind::JaroWinkler => Arc::new(JaroWinkler),
+            ScorerKind::TokenSet => Arc::new(TokenSet),
+            ScorerKind::LengthAdaptive => Arc::new(LengthAdaptive::default()),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ScorerKind::DamerauLevenshtein => "damerau-levenshtein",
+            ScorerKind::JaroWinkler => "jaro-winkler",
+            ScorerKind::TokenSet => "token-set",
+            ScorerKind::LengthAdaptive => "length-adaptive",
+        }
+    }
+}
+
+impl fmt::Display for ScorerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ScorerKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase().replace(['_', ' '], "-");
+        ScorerKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or(Error::UnknownScorer(s))
+    }
+}
@@ -1,15 +1,101 @@
-use crate::quiz::SongArtistQuiz;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::quiz::{romaji, Rules, ScoreSettings, Scorer, ScorerKind, SongArtistQuiz, SongInfo};
+use crate::Error;
 use itertools::Either;
+use unicode_normalization::char::is_combining_mark;
 use unicode_normalization::UnicodeNormalization;
 
 #[derive(Clone)]
 pub struct GuessSettings {
     pub answer_threshold: f32,
     pub display_threshold: f32,
-    split_re: regex::Regex,
+    pub(crate) split_re: regex::Regex,
     pub space_sensitive: bool,
     pub case_sensitive: bool,
     pub punc_sensitive: bool,
+    // also compare kana and latin text in a common romanised form
+    pub transliterate: bool,
+    pub scoring: ScoreSettings,
+    // what players are guessing
+    pub targets: GuessTargets,
+    // how similar a guess is to the answer
+    pub scorer: Arc<dyn Scorer>,
+    // rules applied to answers and guesses before normalising
+    pub rules: Arc<Rules>,
+    // other accepted names for each artist (nicknames, unit names, stylised spellings)
+    //  keyed by `alias_key` of the artist name
+    pub(crate) artist_aliases: Arc<HashMap<String, Vec<String>>>,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GuessTargets {
+    pub song_name: bool,
+    pub artist: bool,
+    pub anime: bool,
+    pub song_type: bool,
+    pub vintage: bool,
+    // pick the song from a list of choices instead of typing it
+    pub choice: bool,
+}
+
+impl Default for GuessTargets {
+    fn default() -> Self {
+        GuessTargets {
+            song_name: true,
+            artist: true,
+            anime: false,
+            song_type: false,
+            vintage: false,
+            choice: false,
+        }
+    }
+}
+
+// A name normalised for comparison
+#[derive(Debug, Clone)]
+pub(crate) struct Name {
+    pub normalised: String,
+    // romanised form, only if transliterating and it differs from the normalised name
+    pub romanised: Option<String>,
+    // sorted characters of each form, for a quick bound on how different two names are
+    chars: Vec<char>,
+    romanised_chars: Option<Vec<char>>,
+}
+
+impl Name {
+    // the romanised form if there is one, otherwise the normalised form
+    fn romanised_form(&self) -> (&str, &[char]) {
+        match (&self.romanised, &self.romanised_chars) {
+            (Some(r), Some(c)) => (r, c),
+            _ => (&self.normalised, &self.chars),
+        }
+    }
+}
+
+// Normalised forms of the text guessed during a song, since chat repeats itself a lot
+#[derive(Default)]
+pub(crate) struct NormalisedGuesses {
+    names: HashMap<String, Arc<[Name]>>,
+}
+
+impl NormalisedGuesses {
+    // more than the different messages in a busy chat during a song
+    const CAPACITY: usize = 4096;
+
+    pub(crate) fn get(&mut self, settings: &GuessSettings, text: &str) -> Arc<[Name]> {
+        if let Some(names) = self.names.get(text) {
+            return names.clone();
+        }
+        if self.names.len() >= Self::CAPACITY {
+            self.names.clear();
+        }
+        let names: Arc<[Name]> = settings.normalise_sn(text).into();
+        self.names.insert(text.to_string(), names.clone());
+        names
+    }
 }
 
 impl Default for GuessSettings {
@@ -23,66 +109,291 @@ impl Default for GuessSettings {
             space_sensitive: false,
             case_sensitive: false,
             punc_sensitive: false,
+            transliterate: true,
+            scoring: ScoreSettings::default(),
+            targets: GuessTargets::default(),
+            scorer: ScorerKind::DamerauLevenshtein.scorer(),
+            rules: Arc::new(Rules::default()),
+            artist_aliases: Arc::new(HashMap::new()),
         }
     }
 }
 
 impl GuessSettings {
-    pub fn check_sn(&self, guess: &str, target: &str) -> f32 {
-        let guess = guess.trim();
-        let target = target.trim();
-        strsim::normalized_damerau_levenshtein(guess, target) as f32
-    }
-
-    pub fn check_artist(&self, guess: &str, target: &(String, Option<String>)) -> f32 {
-        let guess = guess.trim();
-        let s1 = strsim::normalized_damerau_levenshtein(guess, target.0.trim()) as f32;
-        if let Some(ref t2) = target.1 {
-            let s2 = strsim::normalized_damerau_levenshtein(guess, t2.trim()) as f32;
-            s1.max(s2)
-        } else {
-            s1
+    // Best similarity between any form of the guess and any form of the song name.
+    // Only scores above `min` are worked out exactly, anything else may be 0
+    pub(crate) fn check_sn(&self, guess: &[Name], target: &[Name], min: f32) -> f32 {
+        self.best_similarity(guess, target, min)
+    }
+
+    // Best similarity against any of the accepted names for an artist
+    pub(crate) fn check_artist(&self, guess: &[Name], target: &[Name], min: f32) -> f32 {
+        self.best_similarity(guess, target, min)
+    }
+
+    fn best_similarity(&self, guess: &[Name], target: &[Name], min: f32) -> f32 {
+        itertools::iproduct!(guess, target).fold(0.0, |best, (g, t)| {
+            best.max(self.similarity(g, t, min.max(best)))
+        })
+    }
+
+    // Compares both the normalised and romanised forms, taking the best
+    fn similarity(&self, guess: &Name, target: &Name, min: f32) -> f32 {
+        let score = self.compare_above(
+            (&guess.normalised, &guess.chars),
+            (&target.normalised, &target.chars),
+            min,
+        );
+        if guess.romanised.is_none() && target.romanised.is_none() {
+            return score;
         }
+        let min = min.max(score);
+        score.max(self.compare_above(guess.romanised_form(), target.romanised_form(), min))
     }
 
-    fn normalise(&self, it: impl Iterator<Item = char>) -> String {
-        let it = it.nfd();
+    // Skips comparing texts too different to score more than `min`
+    fn compare_above(&self, guess: (&str, &[char]), target: (&str, &[char]), min: f32) -> f32 {
+        let edits = min_edits(guess.1, target.1);
+        if self.scorer.max_score(edits, guess.1.len(), target.1.len()) <= min {
+            return 0.0;
+        }
+        self.scorer
+            .score_above(guess.0.trim(), target.0.trim(), min)
+    }
+
+    pub(crate) fn compare(&self, guess: &str, target: &str) -> f32 {
+        self.scorer.score(guess.trim(), target.trim())
+    }
+
+    // Similarity of a song name guess to the answer, as it would be scored in a quiz
+    pub fn score_song_name(&self, guess: &str, answer: &str) -> f32 {
+        self.check_sn(&self.normalise_sn(guess), &self.normalise_sn(answer), 0.0)
+    }
+
+    // Similarity of an artist guess to a single artist, as it would be scored in a quiz
+    pub fn score_artist(&self, guess: &str, artist: &str) -> f32 {
+        self.check_artist(
+            &self.normalise_sn(guess),
+            &self.normalise_artist(artist),
+            0.0,
+        )
+    }
+
+    pub fn set_scorer(&mut self, kind: ScorerKind) {
+        self.scorer = kind.scorer();
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = Arc::new(rules);
+    }
+
+    // Normalises text so that it can be compared regardless of script width, accents or kana type
+    //  - compatibility decomposition folds full-width latin to half-width,
+    //    half-width katakana to full-width, and splits accents off their letters
+    //  - latin accents are dropped, other combining marks (dakuten, vowel signs, viramas) are kept
+    //  - katakana is folded to hiragana
+    //  - letters and digits of any script are kept, punctuation only if punctuation sensitive
+    pub(crate) fn normalise(&self, it: impl Iterator<Item = char>) -> String {
+        let it = it.nfkd();
         let it = if self.case_sensitive {
             Either::Left(it)
         } else {
             Either::Right(it.flat_map(|c| c.to_lowercase()))
         };
         let condition = |c: &char| -> bool {
-            (if self.punc_sensitive {
-                c.is_ascii()
+            if is_combining_mark(*c) {
+                // accents are already dropped, the rest are part of the letters
+                true
+            } else if c.is_whitespace() {
+                self.space_sensitive
+            } else if self.punc_sensitive {
+                !c.is_control()
             } else {
-                c.is_ascii_alphanumeric()
-            }) && (self.space_sensitive || !c.is_whitespace())
+                c.is_alphanumeric()
+            }
         };
-        it.filter(condition).collect::<String>()
+        it.filter(|&c| !is_accent(c))
+            .map(fold_kana)
+            .filter(condition)
+            .nfc()
+            .collect::<String>()
     }
 
-    pub(crate) fn normalise_sn(&self, sn: &str) -> String {
-        self.normalise(sn.chars())
+    // The text after applying the rules, with and without any optional parts
+    pub(crate) fn normalise_sn(&self, sn: &str) -> Vec<Name> {
+        self.rules
+            .variants(sn)
+            .iter()
+            .map(|v| self.name(self.normalise(v.chars())))
+            .collect()
     }
 
-    pub(crate) fn normalise_artists(&self, artist: &str) -> Vec<(String, Option<String>)> {
+    pub(crate) fn normalise_artists(&self, artist: &str) -> Vec<Vec<Name>> {
         self.split_re
             .split(artist)
             .map(|a| self.normalise_artist(a))
             .collect()
     }
 
-    fn normalise_artist(&self, artist: &str) -> (String, Option<String>) {
-        let reversed = if let Some((first, last)) = artist.rsplit_once(' ') {
-            let (first, last) = (first.trim(), last.trim());
-            let name = self.normalise(last.nfd().chain(std::iter::once(' ')).chain(first.nfd()));
-            Some(name)
+    // The artist name and each of its aliases
+    fn normalise_artist(&self, artist: &str) -> Vec<Name> {
+        let mut names = Vec::new();
+        self.push_artist_names(&mut names, artist);
+        for alias in self.aliases(artist) {
+            self.push_artist_names(&mut names, alias);
+        }
+        names
+    }
+
+    // The name as written, and with the first and last names swapped,
+    //  for each form of the name after applying the rules
+    pub(crate) fn push_artist_names(&self, names: &mut Vec<Name>, artist: &str) {
+        for artist in self.rules.variants(artist) {
+            names.push(self.name(self.normalise(artist.chars())));
+            if let Some((first, last)) = artist.trim().rsplit_once(' ') {
+                let (first, last) = (first.trim(), last.trim());
+                let name = self.normalise(last.chars().chain(std::iter::once(' ')).chain(first.chars()));
+                names.push(self.name(name));
+            }
+        }
+    }
+
+    // Accepts any of the aliases as a correct guess for the artist
+    pub fn add_artist_aliases<I, S>(&mut self, artist: &str, aliases: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let key = alias_key(artist);
+        if key.is_empty() {
+            return;
+        }
+        let map = Arc::make_mut(&mut self.artist_aliases);
+        let list = map.entry(key).or_default();
+        for alias in aliases {
+            let alias = alias.into();
+            if !list.contains(&alias) {
+                list.push(alias);
+            }
+        }
+    }
+
+    // The settings with the aliases for a single song added
+    pub(crate) fn with_song_aliases(mut self, aliases: &[(String, String)]) -> GuessSettings {
+        for (artist, alias) in aliases {
+            self.add_artist_aliases(artist, [alias.as_str()]);
+        }
+        self
+    }
+
+    pub(crate) fn aliases(&self, artist: &str) -> &[String] {
+        self.artist_aliases
+            .get(&alias_key(artist))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn clear_artist_aliases(&mut self) {
+        self.artist_aliases = Arc::new(HashMap::new());
+    }
+
+    pub(crate) fn name(&self, normalised: String) -> Name {
+        let romanised = if self.transliterate {
+            Some(romaji::romanise(&normalised)).filter(|r| *r != normalised)
         } else {
             None
         };
-        let name = self.normalise(artist.nfd());
-        (name, reversed)
+        Name {
+            chars: sorted_chars(&normalised),
+            romanised_chars: romanised.as_deref().map(sorted_chars),
+            normalised,
+            romanised,
+        }
+    }
+}
+
+// Characters as compared by the scorers, sorted
+fn sorted_chars(text: &str) -> Vec<char> {
+    let mut chars: Vec<char> = text.trim().chars().collect();
+    chars.sort_unstable();
+    chars
+}
+
+// Fewest edits that could turn one text into the other, given their sorted characters:
+//  each character only one of them has needs an edit,
+//  and a substitution can only fix one on each side
+fn min_edits(a: &[char], b: &[char]) -> usize {
+    let (mut i, mut j) = (0, 0);
+    let (mut only_a, mut only_b) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => {
+                only_a += 1;
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                only_b += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    only_a += a.len() - i;
+    only_b += b.len() - j;
+    only_a.max(only_b)
+}
+
+// Key used to look up the aliases for an artist, so that they don't depend on
+//  the sensitivity settings or which order the names are written in
+fn alias_key(artist: &str) -> String {
+    let mut words: Vec<String> = artist
+        .split_whitespace()
+        .map(|w| {
+            w.nfkc()
+                .flat_map(char::to_lowercase)
+                .filter(|c| c.is_alphanumeric())
+                .collect()
+        })
+        .filter(|w: &String| !w.is_empty())
+        .collect();
+    words.sort_unstable();
+    words.join(" ")
+}
+
+// Combining diacritics used by latin (and greek/cyrillic) letters
+//  marks of other scripts change the letter itself, so are kept
+fn is_accent(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{FE20}'..='\u{FE2F}')
+}
+
+// Whether normalising changes the character other than its case,
+//  by dropping accents or folding its width or kana
+pub(crate) fn is_folded(c: char) -> bool {
+    std::iter::once(c)
+        .nfkd()
+        .filter(|&c| !is_accent(c))
+        .map(fold_kana)
+        .nfc()
+        .ne(std::iter::once(c))
+}
+
+// Maps katakana to the equivalent hiragana, leaving everything else as is
+fn fold_kana(c: char) -> char {
+    match c {
+        // ァ..=ヶ and ヽ, ヾ
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}' | '\u{30FE}' => {
+            char::from_u32(c as u32 - 0x60).unwrap_or(c)
+        }
+        // the spacing voicing marks (from half-width katakana) to the combining ones
+        '\u{309B}' => '\u{3099}',
+        '\u{309C}' => '\u{309A}',
+        _ => c,
     }
 }
 
@@ -102,6 +413,71 @@ impl SongArtistQuiz {
         guard.punc_sensitive = status;
     }
 
+    pub fn set_transliterate(&self, status: bool) {
+        let mut guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard.transliterate = status;
+    }
+
+    pub fn add_artist_aliases<I, S>(&self, artist: &str, aliases: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard.add_artist_aliases(artist, aliases);
+    }
+
+    // Each of the artists credited, as they are split when guessing
+    pub fn split_artists(&self, artist: &str) -> Vec<String> {
+        let guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard
+            .split_re
+            .split(artist)
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    // Sets aliases that only apply to the one song, e.g. from the database
+    pub fn set_artist_aliases(
+        &self,
+        song_number: i64,
+        aliases: Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        let mut map = self.song_info.lock().expect("mutex poisoned");
+        match map.get_mut(&song_number) {
+            Some(SongInfo::Info {
+                ref mut artist_aliases,
+                ..
+            }) => {
+                *artist_aliases = aliases;
+                Ok(())
+            }
+            _ => Err(Error::NoSongInfo(song_number)),
+        }
+    }
+
+    pub fn set_scorer(&self, kind: ScorerKind) {
+        let mut guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard.set_scorer(kind);
+    }
+
+    pub fn targets(&self) -> GuessTargets {
+        let guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard.targets
+    }
+
+    pub fn set_targets(&self, targets: GuessTargets) {
+        let mut guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard.targets = targets;
+    }
+
+    pub fn set_rules(&self, rules: Rules) {
+        let mut guard = self.guess_settings.lock().expect("mutex poisoned");
+        guard.set_rules(rules);
+    }
+
     pub fn toggle_case_sensitive(&self) -> bool {
         let mut guard = self.guess_settings.lock().expect("mutex poisoned");
         guard.case_sensitive = !guard.case_sensitive;
@@ -120,3 +496,57 @@ impl SongArtistQuiz {
         return guard.punc_sensitive;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalise(text: &str) -> String {
+        GuessSettings::default().normalise(text.chars())
+    }
+
+    #[test]
+    fn normalise_kana() {
+        assert_eq!(normalise("カタカナ"), normalise("かたかな"));
+        assert_eq!(normalise("ガッコウ"), "がっこう");
+        assert_eq!(normalise("パン"), "ぱん");
+        assert_ne!(normalise("はん"), normalise("ぱん"));
+        assert_ne!(normalise("はん"), normalise("ばん"));
+    }
+
+    #[test]
+    fn normalise_kanji() {
+        assert_eq!(normalise("残酷な天使のテーゼ"), "残酷な天使のてーぜ");
+        assert_ne!(normalise("天使"), normalise("天史"));
+    }
+
+    #[test]
+    fn normalise_width() {
+        assert_eq!(normalise("ＡＢＣ１２３"), "abc123");
+        assert_eq!(normalise("ｶﾞｯｺｳ"), normalise("ガッコウ"));
+        assert_eq!(normalise("ﾊﾟﾝ"), "ぱん");
+    }
+
+    #[test]
+    fn normalise_hangul() {
+        assert_eq!(normalise("사랑해"), "사랑해");
+        assert_ne!(normalise("사랑"), normalise("사람"));
+    }
+
+    #[test]
+    fn normalise_latin_accents() {
+        assert_eq!(normalise("Pokémon"), "pokemon");
+        assert_eq!(normalise("Ña Ü Ø ç"), normalise("na u ø c"));
+    }
+
+    #[test]
+    fn normalise_keeps_other_marks() {
+        // devanagari vowel signs and virama
+        assert_eq!(normalise("नमस्ते"), "नमस्ते");
+        assert_ne!(normalise("कि"), normalise("क"));
+        assert_ne!(normalise("क्"), normalise("क"));
+        // thai vowel signs and tone marks
+        assert_eq!(normalise("สวัสดี"), "สวัสดี");
+        assert_ne!(normalise("ไม้"), normalise("ไม"));
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::quiz::{
+    BestGuess, Choices, GuessInfo, GuessSettings, GuessTargets, ScoreSettings, ScorerKind,
+    SongArtistQuiz, SongInfo, Standings, Teams,
+};
+use crate::Error;
+
+// Bumped whenever the snapshot format changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// The state of a quiz, to carry on with it later (e.g. after a restart).
+// Normalisation rules and custom scorers aren't included,
+//  they come from the settings given when restoring
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub song_number: i64,
+    history_len: usize,
+    songs: BTreeMap<i64, SongInfo>,
+    settings: SettingsSnapshot,
+    // seconds into the current song
+    song_time: f64,
+    current: Option<CurrentSnapshot>,
+    scoreboard: Standings,
+    team_scoreboard: Standings,
+    teams: Teams,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct SettingsSnapshot {
+    answer_threshold: f32,
+    display_threshold: f32,
+    space_sensitive: bool,
+    case_sensitive: bool,
+    punc_sensitive: bool,
+    transliterate: bool,
+    scoring: ScoreSettings,
+    targets: GuessTargets,
+    // only set for the built in scorers
+    scorer: Option<ScorerKind>,
+    artist_aliases: HashMap<String, Vec<String>>,
+}
+
+// Guesses and hints so far for the current song
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct CurrentSnapshot {
+    global_best_guess: BestGuess,
+    player_guesses: HashMap<String, BestGuess>,
+    team_guesses: HashMap<String, BestGuess>,
+    hint_seed: u64,
+    hint_times: Vec<f32>,
+    choices: Option<Choices>,
+}
+
+impl SettingsSnapshot {
+    fn new(settings: &GuessSettings) -> Self {
+        SettingsSnapshot {
+            answer_threshold: settings.answer_threshold,
+            display_threshold: settings.display_threshold,
+            space_sensitive: settings.space_sensitive,
+            case_sensitive: settings.case_sensitive,
+            punc_sensitive: settings.punc_sensitive,
+            transliterate: settings.transliterate,
+            scoring: settings.scoring.clone(),
+            targets: settings.targets,
+            scorer: settings.scorer.name().parse().ok(),
+            artist_aliases: (*settings.artist_aliases).clone(),
+        }
+    }
+
+    fn apply(self, settings: &mut GuessSettings) {
+        settings.answer_threshold = self.answer_threshold;
+        settings.display_threshold = self.display_threshold;
+        settings.space_sensitive = self.space_sensitive;
+        settings.case_sensitive = self.case_sensitive;
+        settings.punc_sensitive = self.punc_sensitive;
+        settings.transliterate = self.transliterate;
+        settings.scoring = self.scoring;
+        settings.targets = self.targets;
+        if let Some(kind) = self.scorer {
+            settings.set_scorer(kind);
+        }
+        settings.artist_aliases = Arc::new(self.artist_aliases);
+    }
+}
+
+impl SongArtistQuiz {
+    pub fn snapshot(&self) -> Snapshot {
+        let song_time = self.curr_song_timer();
+        // same lock order as `set_song_number`
+        let songs = self.song_info.lock().expect("mutex poisoned");
+        let curr = self.curr_info.lock().expect("mutex poisoned");
+        let settings = self.guess_settings.lock().expect("mutex poisoned");
+        let scoreboard = self.scoreboard.lock().expect("mutex poisoned");
+        let teams = self.teams.lock().expect("mutex poisoned");
+
+        let current = curr.as_ref().map(|info| CurrentSnapshot {
+            global_best_guess: info.global_best_guess.clone(),
+            player_guesses: info.player_guesses.clone(),
+            team_guesses: info.team_guesses.clone(),
+            hint_seed: info.hints.seed,
+            hint_times: info.hints.times.clone(),
+            choices: info.choices.clone(),
+        });
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            song_number: self.curr_song_number(),
+            history_len: self.history_len.load(Ordering::Acquire),
+            songs: songs.clone(),
+            settings: SettingsSnapshot::new(&settings),
+            song_time,
+            current,
+            scoreboard: scoreboard.players.clone(),
+            team_scoreboard: scoreboard.teams.clone(),
+            teams: teams.clone(),
+        }
+    }
+
+    // Carries on a quiz from a snapshot, taking the rules
+    //  (and the scorer, if it wasn't a built in one) from `settings`
+    pub fn from_snapshot(snapshot: Snapshot, mut settings: GuessSettings) -> Result<Self, Error> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::UnsupportedSnapshotVersion(snapshot.version));
+        }
+        snapshot.settings.apply(&mut settings);
+
+        let quiz = SongArtistQuiz::new_with_settings(settings.clone());
+        quiz.set_history_len(snapshot.history_len);
+        quiz.song_number
+            .store(snapshot.song_number, Ordering::Release);
+
+        // the answers are normalised again, then the guesses put back
+        let info = snapshot
+            .current
+            .zip(snapshot.songs.get(&snapshot.song_number))
+            .and_then(|(current, song)| {
+                let mut info = GuessInfo::from_song_info(song, settings)?;
+                info.global_best_guess = current.global_best_guess;
+                info.player_guesses = current.player_guesses;
+                info.team_guesses = current.team_guesses;
+                info.hints.seed = current.hint_seed;
+                info.hints.times = current.hint_times;
+                if info.choices.is_some() {
+                    info.choices = current.choices;
+                }
+                Some(info)
+            });
+        *quiz.curr_info.lock().expect("mutex poisoned") = info;
+        *quiz.song_info.lock().expect("mutex poisoned") = snapshot.songs;
+        {
+            let mut scoreboard = quiz.scoreboard.lock().expect("mutex poisoned");
+            scoreboard.players = snapshot.scoreboard;
+            scoreboard.teams = snapshot.team_scoreboard;
+        }
+        quiz.set_teams(snapshot.teams);
+        quiz.set_song_timer(snapshot.song_time);
+        Ok(quiz)
+    }
+}
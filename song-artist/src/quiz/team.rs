@@ -0,0 +1,116 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::quiz::score::{sort_scores, with_song};
+use crate::quiz::{GuessInfo, PlayerScore, SongArtistQuiz};
+use crate::Error;
+
+// Players grouped into teams, where a correct guess from anyone in a team
+//  counts for the whole team
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Teams {
+    // members of each team, including teams nobody has joined yet
+    teams: BTreeMap<String, Vec<String>>,
+    // team of each player
+    players: HashMap<String, String>,
+}
+
+impl Teams {
+    pub fn create(&mut self, team: &str) -> Result<(), Error> {
+        let team = team.trim();
+        if team.is_empty() {
+            return Err(Error::EmptyTeamName);
+        }
+        if self.teams.contains_key(team) {
+            return Err(Error::TeamExists(team.to_string()));
+        }
+        self.teams.insert(team.to_string(), Vec::new());
+        Ok(())
+    }
+
+    // Moves the player to the team, out of any team they were in
+    pub fn join(&mut self, player: &str, team: &str) -> Result<(), Error> {
+        let team = team.trim();
+        if !self.teams.contains_key(team) {
+            return Err(Error::NoSuchTeam(team.to_string()));
+        }
+        self.leave(player);
+        self.teams
+            .get_mut(team)
+            .expect("team checked above")
+            .push(player.to_string());
+        self.players.insert(player.to_string(), team.to_string());
+        Ok(())
+    }
+
+    // Returns the team the player left, if they were in one
+    pub fn leave(&mut self, player: &str) -> Option<String> {
+        let team = self.players.remove(player)?;
+        if let Some(members) = self.teams.get_mut(&team) {
+            members.retain(|p| p != player);
+        }
+        Some(team)
+    }
+
+    pub fn team(&self, player: &str) -> Option<&str> {
+        self.players.get(player).map(String::as_str)
+    }
+
+    // Each team and its members, in order of team name
+    pub fn list(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.teams.iter().map(|(t, m)| (t.as_str(), m.as_slice()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.teams.is_empty()
+    }
+}
+
+impl GuessInfo {
+    // Starts the team's best guesses for the song, if it hasn't already
+    pub(crate) fn add_team(&mut self, team: Option<&str>) {
+        if let Some(team) = team {
+            if !self.team_guesses.contains_key(team) {
+                let best = self.new_best_guess();
+                self.team_guesses.insert(team.to_string(), best);
+            }
+        }
+    }
+}
+
+impl SongArtistQuiz {
+    pub(crate) fn player_team(&self, player: &str) -> Option<String> {
+        let teams = self.teams.lock().expect("mutex poisoned");
+        teams.team(player).map(str::to_string)
+    }
+
+    pub fn teams(&self) -> Teams {
+        self.teams.lock().expect("mutex poisoned").clone()
+    }
+
+    // Players changing team keep what they guessed for their old team this song
+    pub fn set_teams(&self, teams: Teams) {
+        *self.teams.lock().expect("mutex poisoned") = teams;
+    }
+
+    // Points each team has scored in the current song so far,
+    //  with the team name in place of the player
+    pub fn team_song_scores(&self) -> Vec<PlayerScore> {
+        let guard = self.curr_info.lock().expect("mutex poisoned");
+        let scoreboard = self.scoreboard.lock().expect("mutex poisoned");
+        let mut scores: Vec<_> = guard
+            .iter()
+            .flat_map(|info| info.scores(&info.team_guesses, &scoreboard.teams.streaks))
+            .collect();
+        sort_scores(&mut scores);
+        scores
+    }
+
+    // Total points for each team, including the current song
+    pub fn team_scoreboard(&self) -> Vec<PlayerScore> {
+        let totals = {
+            let guard = self.scoreboard.lock().expect("mutex poisoned");
+            guard.teams.totals.clone()
+        };
+        with_song(totals, self.team_song_scores())
+    }
+}
@@ -1,11 +1,13 @@
 mod cancel;
 mod error;
 mod extrait;
+mod metadata;
 mod song;
 mod stream; // fk the french
 
 pub use cancel::Cancellable;
 pub use error::Error;
 pub use extrait::{Sample, SamplePosition};
+pub use metadata::{CoverArt, ReplayGain, SongMetadata};
 pub use song::{Message, SongReader};
 pub use stream::StreamDownloadFile;
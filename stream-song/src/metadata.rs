@@ -0,0 +1,142 @@
+use symphonia::core::meta::{
+    Limit, MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey, Value,
+};
+
+// Cover art larger than this is skipped when reading the tags
+const MAX_VISUAL_BYTES: usize = 8 * 1024 * 1024;
+
+// Tags read from the container (MP4 atoms) or embedded metadata (ID3)
+#[derive(Debug, Clone, Default)]
+pub struct SongMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub cover_art: Option<CoverArt>,
+    pub replay_gain: ReplayGain,
+}
+
+#[derive(Debug, Clone)]
+pub struct CoverArt {
+    // MIME type of the image, e.g. `image/jpeg`
+    pub media_type: String,
+    pub data: Box<[u8]>,
+}
+
+// Gains are in dB, peaks are linear amplitude
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+impl SongMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.album.is_none()
+            && self.cover_art.is_none()
+            && self.replay_gain.is_empty()
+    }
+
+    // Later revisions overwrite any tags that were already read
+    pub(crate) fn merge_revision(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let value = || tag_string(&tag.value);
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => self.title = value(),
+                Some(StandardTagKey::Artist) => self.artist = value(),
+                Some(StandardTagKey::Album) => self.album = value(),
+                Some(StandardTagKey::ReplayGainTrackGain) => {
+                    self.replay_gain.track_gain = parse_gain(&tag.value)
+                }
+                Some(StandardTagKey::ReplayGainTrackPeak) => {
+                    self.replay_gain.track_peak = parse_gain(&tag.value)
+                }
+                Some(StandardTagKey::ReplayGainAlbumGain) => {
+                    self.replay_gain.album_gain = parse_gain(&tag.value)
+                }
+                Some(StandardTagKey::ReplayGainAlbumPeak) => {
+                    self.replay_gain.album_peak = parse_gain(&tag.value)
+                }
+                _ => {}
+            }
+        }
+
+        // Prefer the front cover, otherwise take whatever picture comes first
+        let visuals = revision.visuals();
+        let visual = visuals
+            .iter()
+            .find(|v| v.usage == Some(StandardVisualKey::FrontCover))
+            .or_else(|| visuals.first());
+        if let Some(v) = visual {
+            self.cover_art = Some(CoverArt {
+                media_type: v.media_type.clone(),
+                data: v.data.clone(),
+            });
+        }
+    }
+}
+
+impl CoverArt {
+    // File extension to use when uploading the image
+    pub fn extension(&self) -> &str {
+        match self.media_type.as_str() {
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/bmp" => "bmp",
+            _ => "jpg",
+        }
+    }
+}
+
+impl ReplayGain {
+    pub fn is_empty(&self) -> bool {
+        self.track_gain.is_none()
+            && self.track_peak.is_none()
+            && self.album_gain.is_none()
+            && self.album_peak.is_none()
+    }
+}
+
+pub(crate) fn metadata_options() -> MetadataOptions {
+    MetadataOptions {
+        limit_metadata_bytes: Limit::Default,
+        limit_visual_bytes: Limit::Maximum(MAX_VISUAL_BYTES),
+    }
+}
+
+fn tag_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Binary(_) | Value::Flag => None,
+        v => {
+            let s = v.to_string();
+            let s = s.trim();
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.to_string())
+            }
+        }
+    }
+}
+
+// ReplayGain values are usually strings like "-6.48 dB"
+fn parse_gain(value: &Value) -> Option<f32> {
+    match value {
+        Value::Float(f) => Some(*f as f32),
+        Value::SignedInt(i) => Some(*i as f32),
+        Value::UnsignedInt(i) => Some(*i as f32),
+        Value::String(s) => {
+            let s = s.trim();
+            let s = s
+                .strip_suffix("dB")
+                .or_else(|| s.strip_suffix("db"))
+                .unwrap_or(s);
+            s.trim().parse().ok()
+        }
+        _ => None,
+    }
+}
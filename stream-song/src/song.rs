@@ -10,11 +10,11 @@ use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
 use symphonia::core::io::{MediaSource, MediaSourceStream};
-use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
 use rubato::{FftFixedIn, Resampler};
 
+use crate::metadata::{metadata_options, SongMetadata};
 use crate::{extrait, Cancellable, Error, SamplePosition};
 
 // TODO: max size - this uses 22MB per minute of audio?
@@ -126,6 +126,8 @@ pub enum Message {
     StartSample(symphonia::core::units::Time),
     TotalDuration(symphonia::core::units::Time),
     Update(symphonia::core::units::Time),
+    // Tags and cover art read from the file, only sent if there are any
+    Metadata(Box<SongMetadata>),
     DecodeError(Error),
 }
 
@@ -169,16 +171,29 @@ fn decode_data<R: MediaSource + 'static, W: io::Write>(
     let mut hint = Hint::new();
     hint.with_extension("mp3"); // TODO: actually pass in the extension
 
-    // Use the default options for metadata and format readers.
-    let meta_opts: MetadataOptions = Default::default();
+    // Use the default options for the format reader.
     let fmt_opts: FormatOptions = Default::default();
 
     // this function blocks to read?
-    let probed = symphonia::default::get_probe()
-        .format(&hint, mss, &fmt_opts, &meta_opts)
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, mss, &fmt_opts, &metadata_options())
         .map_err(Error::ProbeFormatError)?;
 
+    // Tags can be found either before the container (ID3) or inside it (MP4)
+    let mut metadata = SongMetadata::default();
+    if let Some(mut m) = probed.metadata.get() {
+        if let Some(rev) = m.skip_to_latest() {
+            metadata.merge_revision(rev);
+        }
+    }
+
     let mut format = probed.format;
+    if let Some(rev) = format.metadata().skip_to_latest() {
+        metadata.merge_revision(rev);
+    }
+    if !metadata.is_empty() {
+        let _ = tx.try_send(Message::Metadata(Box::new(metadata)));
+    }
 
     // Find the first audio track with a known (decodeable) codec.
     // should use default_track method?